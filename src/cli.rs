@@ -1,7 +1,6 @@
 // cli: Handle command line parsing
 #![forbid(unsafe_code)]
 #![forbid(missing_docs)]
-use super::products::PRODUCTS_LIST;
 use clap::{
     crate_description,
     crate_name,
@@ -12,12 +11,22 @@ use clap::{
     ColorChoice,
     Command,
 };
-use clap::builder::PossibleValuesParser;
+use clap::builder::{
+    ArgPredicate,
+    NonEmptyStringValueParser,
+    PossibleValuesParser,
+};
+use hcdl::{
+    install,
+    signature,
+};
 use std::env;
+use std::fs;
 use std::path::{
     Path,
     PathBuf,
 };
+use url::Url;
 
 #[cfg(feature = "shell_completion")]
 use clap::builder::EnumValueParser;
@@ -28,9 +37,14 @@ use clap_complete::generate;
 #[cfg(feature = "shell_completion")]
 use clap_complete::Shell;
 
-#[cfg(feature = "shell_completion")]
+#[cfg(any(feature = "man_page", feature = "shell_completion"))]
+use std::io::Write;
+
+#[cfg(feature = "man_page")]
 use std::io;
 
+use crate::products;
+
 #[cfg(target_arch = "arm")]
 pub const DEFAULT_ARCH: &str = "arm";
 
@@ -40,10 +54,15 @@ pub const DEFAULT_ARCH: &str = "386";
 #[cfg(target_arch = "x86_64")]
 pub const DEFAULT_ARCH: &str = "amd64";
 
+#[cfg(target_arch = "aarch64")]
+pub const DEFAULT_ARCH: &str = "arm64";
+
 const VALID_ARCH: &[&str] = &[
     "386",
     "amd64",
     "arm",
+    "arm64",
+    "universal",
 ];
 
 #[cfg(target_os = "freebsd")]
@@ -73,7 +92,13 @@ const VALID_OS: &[&str] = &[
     "windows",
 ];
 
+const DEFAULT_FORMAT: &str = "human";
 const DEFAULT_VERSION: &str = "latest";
+
+const VALID_FORMAT: &[&str] = &[
+    "human",
+    "json",
+];
 const NO_COLOR: &str = "NO_COLOR";
 
 // Checks the environment to see if NO_COLOR is in use.
@@ -81,6 +106,37 @@ pub fn no_color() -> bool {
     env::var_os(NO_COLOR).is_some()
 }
 
+// Ensure that the checksum file can be written, in a directory that exists.
+fn is_valid_checksum_file(s: &str) -> Result<PathBuf, String> {
+    let path = Path::new(&s);
+
+    if path.is_dir() {
+        return Err("checksum-file is a directory".into());
+    }
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _                                        => Path::new("."),
+    };
+
+    if !dir.is_dir() {
+        return Err("checksum-file directory does not exist".into());
+    }
+
+    let writable = if path.exists() {
+        fs::OpenOptions::new().write(true).open(path).is_ok()
+    }
+    else {
+        tempfile::tempfile_in(dir).is_ok()
+    };
+
+    if !writable {
+        return Err("checksum-file is not writable".into());
+    }
+
+    Ok(path.to_path_buf())
+}
+
 // Ensure that the installation dir exists and is a directory.
 fn is_valid_install_dir(s: &str) -> Result<PathBuf, String> {
     let path = Path::new(&s);
@@ -96,8 +152,66 @@ fn is_valid_install_dir(s: &str) -> Result<PathBuf, String> {
     Ok(path.to_path_buf())
 }
 
+// Ensure that the keep dir exists, is a directory, and can be written to.
+fn is_valid_keep_dir(s: &str) -> Result<PathBuf, String> {
+    let path = Path::new(&s);
+
+    if !path.exists() {
+        return Err("keep-dir does not exist".into());
+    }
+
+    if !path.is_dir() {
+        return Err("keep-dir is not a directory".into());
+    }
+
+    if tempfile::tempfile_in(path).is_err() {
+        return Err("keep-dir is not writable".into());
+    }
+
+    Ok(path.to_path_buf())
+}
+
+// Ensure that the key ID is a valid short or long key ID, or fingerprint.
+fn is_valid_key_id(s: &str) -> Result<String, String> {
+    signature::parse_key_id(s).map_err(|err| err.to_string())
+}
+
+// Ensure that the name to install the binary as is a plain file name.
+fn is_valid_rename(s: &str) -> Result<String, String> {
+    if !install::is_safe_file_name(s) {
+        return Err("name must not contain path separators".into());
+    }
+
+    Ok(s.to_string())
+}
+
 #[allow(clippy::too_many_lines)]
 fn create_app() -> Command {
+    // Options that don't need a PRODUCT. Some of these only exist with
+    // certain features, and clap refuses to reference missing args.
+    #[cfg_attr(
+        not(any(feature = "shell_completion", feature = "man_page")),
+        allow(unused_mut),
+    )]
+    let mut no_product = vec![
+        "CHECK_ALL",
+        "FROM_FILE",
+        "GPG_FINGERPRINT",
+        "INSTALLED",
+        "LIST_PRODUCTS",
+        "PRINT_CONFIG",
+        "PRINT_INSTALL_DIR",
+        "REFRESH_GPG_KEY",
+        "REFRESH_PRODUCTS",
+        "SELFTEST",
+    ];
+
+    #[cfg(feature = "shell_completion")]
+    no_product.push("COMPLETIONS");
+
+    #[cfg(feature = "man_page")]
+    no_product.push("MAN");
+
     let app = Command::new(crate_name!())
         .version(crate_version!())
         .about(crate_description!())
@@ -105,6 +219,7 @@ fn create_app() -> Command {
             Arg::new("ARCH")
                 .action(ArgAction::Set)
                 .default_value(DEFAULT_ARCH)
+                .env("HCDL_ARCH")
                 .help("Specify product architecture to download.")
                 .long("arch")
                 .short('a')
@@ -114,11 +229,19 @@ fn create_app() -> Command {
             Arg::new("BUILD")
                 .action(ArgAction::Set)
                 .default_value(DEFAULT_VERSION)
-                .help("Specify product build version to download.")
+                .env("HCDL_BUILD")
+                .help("Specify product build version or version constraint to download.")
                 .long("build")
                 .short('b')
                 .value_name("VERSION")
         )
+        .arg(
+            Arg::new("CHANGELOG")
+                .action(ArgAction::SetTrue)
+                .help("Print the changelog for the version and exit without downloading.")
+                .long("changelog")
+                .conflicts_with("CHECK")
+        )
         .arg(
             Arg::new("CHECK")
                 .action(ArgAction::SetTrue)
@@ -128,6 +251,45 @@ fn create_app() -> Command {
                     "BUILD",
                     "QUIET",
                 ])
+        )
+        .arg(
+            Arg::new("CHECKSUM_FILE")
+                .action(ArgAction::Set)
+                .help("Write the SHA256 of the verified download to FILE, in the format of sha256sum.")
+                .long("checksum-file")
+                .value_name("FILE")
+                .value_parser(is_valid_checksum_file)
+                .conflicts_with_all([
+                    "CHANGELOG",
+                    "CHECK",
+                    "DRY_RUN",
+                    "FROM_FILE",
+                    "LIST_BUILDS",
+                    "NO_VERIFY_CHECKSUM",
+                    "NO_VERIFY_SIGNATURE",
+                    "PRINT_CHECKSUM",
+                    "UNINSTALL",
+                    "USE",
+                    "VERIFY",
+                ])
+        )
+        .arg(
+            Arg::new("CHECK_ALL")
+                .action(ArgAction::SetTrue)
+                .help("Check every installed product for upgrades and exit without downloading.")
+                .long("check-all")
+                .conflicts_with_all([
+                    "BUILD",
+                    "CHANGELOG",
+                    "CHECK",
+                    "DOWNLOAD_ONLY",
+                    "DRY_RUN",
+                    "FROM_FILE",
+                    "KEEP",
+                    "LIST_BUILDS",
+                    "UNINSTALL",
+                    "VERIFY",
+                ])
         );
 
     #[cfg(feature = "shell_completion")]
@@ -138,9 +300,57 @@ fn create_app() -> Command {
                 .long("completions")
                 .value_name("SHELL")
                 .value_parser(EnumValueParser::<Shell>::new())
+        )
+        .arg(
+            Arg::new("INSTALL_COMPLETIONS")
+                .action(ArgAction::SetTrue)
+                .help("Install the shell completions where the shell loads them from, instead of printing them.")
+                .long("install")
+                .requires("COMPLETIONS")
+        );
+
+    #[cfg(feature = "man_page")]
+    let app = app.arg(
+            Arg::new("MAN")
+                .action(ArgAction::SetTrue)
+                .help("Generate a man page")
+                .long("man")
+        );
+
+    #[cfg(feature = "tracing")]
+    let app = app.arg(
+            Arg::new("VERBOSE")
+                .action(ArgAction::Count)
+                .help("Log diagnostics to stderr, -vv for more detail. Overrides RUST_LOG.")
+                .long("verbose")
+                .short('v')
+        );
+
+    #[cfg(target_os = "macos")]
+    let app = app.arg(
+            Arg::new("NO_GATEKEEPER_FIX")
+                .action(ArgAction::SetTrue)
+                .help("Don't clear the quarantine attribute of, or verify the code signature of, the installed product.")
+                .long("no-gatekeeper-fix")
         );
 
     let app = app
+        .arg(
+            Arg::new("CACHE_DIR")
+                .action(ArgAction::Set)
+                .help("Specify directory to cache downloaded zips in.")
+                .long("cache-dir")
+                .value_name("DIR")
+                .value_parser(clap::value_parser!(PathBuf))
+        )
+        .arg(
+            Arg::new("DEADLINE")
+                .action(ArgAction::Set)
+                .help("Give up if hcdl hasn't finished within SECONDS.")
+                .long("deadline")
+                .value_name("SECONDS")
+                .value_parser(clap::value_parser!(u64).range(1..))
+        )
         .arg(
             Arg::new("DOWNLOAD_ONLY")
                 .action(ArgAction::SetTrue)
@@ -148,15 +358,119 @@ fn create_app() -> Command {
                 .long("download-only")
                 .short('D')
         )
+        .arg(
+            Arg::new("DRY_RUN")
+                .action(ArgAction::SetTrue)
+                .help("Report what would be downloaded and installed, without doing it.")
+                .long("dry-run")
+        )
+        .arg(
+            Arg::new("ENSURE_PATH")
+                .action(ArgAction::SetTrue)
+                .help("Add the install dir to the PATH in your shell's rc file if it isn't already in the PATH.")
+                .long("ensure-path")
+                .conflicts_with_all([
+                    "DOWNLOAD_ONLY",
+                    "DRY_RUN",
+                ])
+        )
+        .arg(
+            Arg::new("ENTERPRISE")
+                .action(ArgAction::SetTrue)
+                .help("Download the enterprise variant of the product.")
+                .long("enterprise")
+        )
+        .arg(
+            Arg::new("FORCE")
+                .action(ArgAction::SetTrue)
+                .help("Download and install even if the version is already installed.")
+                .long("force")
+                .short('f')
+        )
+        .arg(
+            Arg::new("FORCE_PROGRESS")
+                .action(ArgAction::SetTrue)
+                .help("Draw download progress bars even when stderr isn't a terminal.")
+                .long("force-progress")
+                .conflicts_with("NO_PROGRESS")
+        )
+        .arg(
+            Arg::new("FORMAT")
+                .action(ArgAction::Set)
+                .default_value(DEFAULT_FORMAT)
+                .help("Specify the output format.")
+                .long("format")
+                .value_parser(PossibleValuesParser::new(VALID_FORMAT))
+        )
+        .arg(
+            Arg::new("FROM_FILE")
+                .action(ArgAction::Set)
+                .help("Install from a local zip FILE instead of downloading.")
+                .long("from-file")
+                .value_name("FILE")
+                .value_parser(clap::value_parser!(PathBuf))
+                .conflicts_with_all([
+                    "BUILD",
+                    "CHANGELOG",
+                    "CHECK",
+                    "DOWNLOAD_ONLY",
+                    "DRY_RUN",
+                    "ENTERPRISE",
+                    "KEEP",
+                    "LIST_BUILDS",
+                    "UNINSTALL",
+                    "VERIFY",
+                    "VERSIONED",
+                ])
+        )
+        .arg(
+            Arg::new("GPG_FINGERPRINT")
+                .action(ArgAction::SetTrue)
+                .help("Print the fingerprint of the GPG key used to verify signatures and exit.")
+                .long("gpg-fingerprint")
+        )
+        .arg(
+            Arg::new("GPG_KEY")
+                .action(ArgAction::Set)
+                .help("Specify an armored or binary GPG key file to verify signatures against.")
+                .long("gpg-key")
+                .value_name("PATH")
+                .value_parser(clap::value_parser!(PathBuf))
+                .conflicts_with("NO_VERIFY_SIGNATURE")
+        )
+        .arg(
+            Arg::new("INCLUDE_PRERELEASE")
+                .action(ArgAction::SetTrue)
+                .help("Consider pre-release versions when finding the latest version or matching a constraint.")
+                .long("include-prerelease")
+        )
         .arg(
             Arg::new("INSTALL_DIR")
                 .action(ArgAction::Set)
+                .env("HCDL_INSTALL_DIR")
                 .help("Specify directory to install product to.")
                 .long("install-dir")
                 .short('d')
                 .value_name("DIR")
                 .value_parser(is_valid_install_dir)
         )
+        .arg(
+            Arg::new("INSTALL_EXTRAS")
+                .action(ArgAction::SetTrue)
+                .help("Install only the product binary as executable, installing any extras in the zip, such as licenses, without the executable bit.")
+                .long("install-extras")
+                .conflicts_with_all([
+                    "DOWNLOAD_ONLY",
+                    "FROM_FILE",
+                    "VERSIONED",
+                ])
+        )
+        .arg(
+            Arg::new("INSTALLED")
+                .action(ArgAction::SetTrue)
+                .help("List the products installed by hcdl and exit.")
+                .long("installed")
+        )
         .arg(
             Arg::new("KEEP")
                 .action(ArgAction::SetTrue)
@@ -164,6 +478,19 @@ fn create_app() -> Command {
                 .long("keep")
                 .short('k')
         )
+        .arg(
+            Arg::new("KEEP_DIR")
+                .action(ArgAction::Set)
+                .help("Specify directory to keep downloaded zipfiles in. Implies --keep.")
+                .long("keep-dir")
+                .value_name("DIR")
+                .value_parser(is_valid_keep_dir)
+                .conflicts_with_all([
+                    "FROM_FILE",
+                    "UNINSTALL",
+                    "VERIFY",
+                ])
+        )
         .arg(
             Arg::new("LIST_PRODUCTS")
                 .action(ArgAction::SetTrue)
@@ -171,39 +498,272 @@ fn create_app() -> Command {
                 .long("list-products")
                 .short('l')
         )
+        .arg(
+            Arg::new("LIST_BUILDS")
+                .action(ArgAction::SetTrue)
+                .help("List every platform build of the version and exit without downloading.")
+                .long("list-builds")
+                .conflicts_with_all([
+                    "CHANGELOG",
+                    "CHECK",
+                    "UNINSTALL",
+                    "VERIFY",
+                ])
+        )
+        .arg(
+            Arg::new("MAX_BYTES_PER_SEC")
+                .action(ArgAction::Set)
+                .help("Limit the download bandwidth to BYTES per second, shared between all downloads.")
+                .long("max-bytes-per-sec")
+                .value_name("BYTES")
+                .value_parser(clap::value_parser!(u64).range(1..))
+        )
+        .arg(
+            Arg::new("MIRROR")
+                .action(ArgAction::Append)
+                .help("Base URL of a mirror of releases.hashicorp.com to fall back to. May be given multiple times.")
+                .long("mirror")
+                .value_name("URL")
+                .value_parser(clap::value_parser!(Url))
+        )
+        .arg(
+            Arg::new("MIRROR_HEADERS")
+                .action(ArgAction::SetTrue)
+                .help("Also send HCDL_API_TOKEN to the --mirror URLs, it's only sent to the releases API by default.")
+                .long("mirror-headers")
+                .requires("MIRROR")
+        )
+        .arg(
+            Arg::new("NO_PROGRESS")
+                .action(ArgAction::SetTrue)
+                .help("Don't draw download progress bars.")
+                .long("no-progress")
+        )
+        .arg(
+            Arg::new("NO_VERIFY_CHECKSUM")
+                .action(ArgAction::SetTrue)
+                .help("Disable SHA256 verification of the download. The shasums signature is still verified, unless --no-verify-signature is also given.")
+                .long("no-verify-checksum")
+                .conflicts_with_all([
+                    "PRINT_CHECKSUM",
+                    "VERIFY",
+                ])
+        )
         .arg(
             Arg::new("NO_VERIFY_SIGNATURE")
                 .action(ArgAction::SetTrue)
                 .help("Disable GPG signature verification.")
                 .long("no-verify-signature")
         )
+        .arg(
+            Arg::new("OFFLINE")
+                .action(ArgAction::SetTrue)
+                .help("Forbid network access, read everything from the cache dir.")
+                .long("offline")
+                .requires("CACHE_DIR")
+        )
         .arg(
             Arg::new("OS")
                 .action(ArgAction::Set)
                 .default_value(DEFAULT_OS)
+                .env("HCDL_OS")
                 .help("Specify product OS family to download.")
                 .long("os")
                 .short('o')
                 .value_parser(PossibleValuesParser::new(VALID_OS))
         )
+        .arg(
+            Arg::new("PRESERVE_STRUCTURE")
+                .action(ArgAction::SetTrue)
+                .help("Recreate the directory layout of the zip under the install dir, instead of installing every file by its basename.")
+                .long("preserve-structure")
+                .conflicts_with_all([
+                    "DOWNLOAD_ONLY",
+                    "FROM_FILE",
+                    "INSTALL_EXTRAS",
+                    "RENAME",
+                    "VERSIONED",
+                ])
+        )
+        .arg(
+            Arg::new("PRINT_CHECKSUM")
+                .action(ArgAction::SetTrue)
+                .help("Print the verified SHA256 of the matching build and exit, without downloading it.")
+                .long("print-checksum")
+                .conflicts_with_all([
+                    "CHANGELOG",
+                    "CHECK",
+                    "DOWNLOAD_ONLY",
+                    "DRY_RUN",
+                    "LIST_BUILDS",
+                    "NO_VERIFY_SIGNATURE",
+                    "UNINSTALL",
+                    "USE",
+                    "VERIFY",
+                ])
+        )
+        .arg(
+            Arg::new("PRINT_CONFIG")
+                .action(ArgAction::SetTrue)
+                .help("Print the effective client configuration and exit.")
+                .long("print-config")
+        )
+        .arg(
+            Arg::new("PRINT_INSTALL_DIR")
+                .action(ArgAction::SetTrue)
+                .help("Print the directory products would be installed to and exit.")
+                .long("print-install-dir")
+        )
         .arg(
             Arg::new("QUIET")
                 .action(ArgAction::SetTrue)
-                .help("Silence all non-error output")
+                .help("Silence all non-error output, except for download progress bars")
                 .long("quiet")
                 .short('q')
         )
+        .arg(
+            Arg::new("REFRESH_GPG_KEY")
+                .action(ArgAction::SetTrue)
+                .help("Fetch the HashiCorp GPG key again, replacing the stored key.")
+                .long("refresh-gpg-key")
+                .conflicts_with_all([
+                    "GPG_KEY",
+                    "OFFLINE",
+                ])
+        )
+        .arg(
+            Arg::new("REFRESH_PRODUCTS")
+                .action(ArgAction::SetTrue)
+                .help("Refresh the cached list of known products from the releases API.")
+                .long("refresh-products")
+                .conflicts_with("OFFLINE")
+        )
+        .arg(
+            Arg::new("RENAME")
+                .action(ArgAction::Set)
+                .help("Install the product binary under this name instead of the product name.")
+                .long("rename")
+                .value_name("NAME")
+                .value_parser(is_valid_rename)
+                .conflicts_with_all([
+                    "DOWNLOAD_ONLY",
+                    "FROM_FILE",
+                    "INSTALL_EXTRAS",
+                    "UNINSTALL",
+                    "VERSIONED",
+                ])
+        )
+        .arg(
+            Arg::new("REQUIRE_KEY")
+                .action(ArgAction::Set)
+                .help("Only accept signatures made by the GPG key with this key ID or fingerprint.")
+                .long("require-key")
+                .value_name("KEYID")
+                .value_parser(is_valid_key_id)
+                .conflicts_with("NO_VERIFY_SIGNATURE")
+        )
+        .arg(
+            Arg::new("SELFTEST")
+                .action(ArgAction::SetTrue)
+                .help("Check connectivity to the releases API, signature verification and the install dir, then exit.")
+                .long("selftest")
+                .conflicts_with("OFFLINE")
+        )
+        .arg(
+            Arg::new("UNINSTALL")
+                .action(ArgAction::SetTrue)
+                .help("Uninstall the product from the install dir.")
+                .long("uninstall")
+                .conflicts_with_all([
+                    "BUILD",
+                    "CHANGELOG",
+                    "CHECK",
+                    "DOWNLOAD_ONLY",
+                    "DRY_RUN",
+                    "KEEP",
+                ])
+        )
+        .arg(
+            Arg::new("USE")
+                .action(ArgAction::Set)
+                .help("Point the 'current' symlink of a versioned install at VERSION, installing it first if needed.")
+                .long("use")
+                .value_name("VERSION")
+                .conflicts_with_all([
+                    "BUILD",
+                    "CHANGELOG",
+                    "CHECK",
+                    "DOWNLOAD_ONLY",
+                    "DRY_RUN",
+                    "INSTALL_EXTRAS",
+                    "LIST_BUILDS",
+                    "UNINSTALL",
+                ])
+        )
+        .arg(
+            Arg::new("VERIFY")
+                .action(ArgAction::Set)
+                .help("Verify an already downloaded FILE against the product's signed shasums and exit.")
+                .long("verify")
+                .value_name("FILE")
+                .value_parser(clap::value_parser!(PathBuf))
+                .conflicts_with_all([
+                    "CHANGELOG",
+                    "CHECK",
+                    "DOWNLOAD_ONLY",
+                    "DRY_RUN",
+                    "KEEP",
+                    "NO_VERIFY_SIGNATURE",
+                    "UNINSTALL",
+                ])
+        )
+        .arg(
+            Arg::new("VERIFY_EXEC")
+                .action(ArgAction::SetTrue)
+                .help("After installing, run the product to check that it reports the installed version.")
+                .long("verify-exec")
+                .conflicts_with_all([
+                    "CHANGELOG",
+                    "CHECK",
+                    "DOWNLOAD_ONLY",
+                    "DRY_RUN",
+                    "FROM_FILE",
+                    "LIST_BUILDS",
+                    "UNINSTALL",
+                    "VERIFY",
+                ])
+        )
+        .arg(
+            Arg::new("VERIFY_WITH")
+                .action(ArgAction::Set)
+                .help("Check the --from-file zip against the local SHASUMS file before installing.")
+                .long("verify-with")
+                .value_name("SHASUMS")
+                .value_parser(clap::value_parser!(PathBuf))
+                .requires("FROM_FILE")
+        )
+        .arg(
+            Arg::new("VERSIONED")
+                .action(ArgAction::SetTrue)
+                .help("Install to a versioned directory under the install dir and point a 'current' symlink at it.")
+                .long("versioned")
+                .conflicts_with("DOWNLOAD_ONLY")
+                .default_value_if("USE", ArgPredicate::IsPresent, Some("true"))
+        )
         // Positional
         .arg(
             Arg::new("PRODUCT")
-                .action(ArgAction::Set)
-                .help("Name of the Hashicorp product to download.")
+                .action(ArgAction::Append)
+                .env("HCDL_PRODUCT")
+                .help("Name of the Hashicorp products to download.")
+                .long_help(format!(
+                    "Name of the Hashicorp products to download.\n\nKnown products: {}.",
+                    products::known().join(", "),
+                ))
                 .index(1)
-                .value_parser(PossibleValuesParser::new(PRODUCTS_LIST))
-                .required_unless_present_any([
-                    "COMPLETIONS",
-                    "LIST_PRODUCTS",
-                ])
+                .num_args(1..)
+                .value_parser(NonEmptyStringValueParser::new())
+                .required_unless_present_any(no_product)
         );
 
     if no_color() {
@@ -219,8 +779,54 @@ pub fn parse_args() -> ArgMatches {
 }
 
 #[cfg(feature = "shell_completion")]
-pub fn gen_completions(shell: Shell) {
+pub fn gen_completions(shell: Shell, out: &mut dyn Write) {
     let mut app = create_app();
 
-    generate(shell, &mut app, crate_name!(), &mut io::stdout());
+    generate(shell, &mut app, crate_name!(), out);
+}
+
+// Write a roff man page, generated from the same Command as the CLI, to
+// `out`.
+#[cfg(feature = "man_page")]
+pub fn gen_man(out: &mut dyn Write) -> io::Result<()> {
+    clap_mangen::Man::new(create_app()).render(out)
+}
+
+// Returns the conventional path that `shell` loads completions from, if
+// installing completions is supported for the `shell`.
+//   - bash: $XDG_DATA_HOME/bash-completion/completions/hcdl
+//   - fish: $XDG_CONFIG_HOME/fish/completions/hcdl.fish
+//   - zsh:  ~/.zfunc/_hcdl, which must be in the zsh fpath
+#[cfg(feature = "shell_completion")]
+pub fn completions_path(shell: Shell) -> Option<PathBuf> {
+    let home = dirs::home_dir()?;
+
+    // Fall back to the XDG defaults, even on macOS where dirs wouldn't.
+    let xdg_dir = |var: &str, default: &str| {
+        env::var_os(var)
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .unwrap_or_else(|| home.join(default))
+    };
+
+    let path = match shell {
+        Shell::Bash => {
+            xdg_dir("XDG_DATA_HOME", ".local/share")
+                .join("bash-completion")
+                .join("completions")
+                .join(crate_name!())
+        },
+        Shell::Fish => {
+            xdg_dir("XDG_CONFIG_HOME", ".config")
+                .join("fish")
+                .join("completions")
+                .join(format!("{}.fish", crate_name!()))
+        },
+        Shell::Zsh => {
+            home.join(".zfunc").join(format!("_{}", crate_name!()))
+        },
+        _ => return None,
+    };
+
+    Some(path)
 }
@@ -1,11 +1,14 @@
 // client: HTTP client and associated methods
 #![forbid(unsafe_code)]
 #![forbid(missing_docs)]
-use serde::Deserialize;
+use serde::{
+    Deserialize,
+    Serialize,
+};
 use url::Url;
 
 /// Represents a single build of a [HashiCorp](https://hashicorp.io) product.
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Build {
     /// The `arch` of the build.
     pub arch: String,
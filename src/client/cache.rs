@@ -0,0 +1,212 @@
+// cache: Cache downloaded artifacts on disk
+#![forbid(unsafe_code)]
+#![forbid(missing_docs)]
+use crate::error::ClientError;
+use crate::shasums::Sha256Writer;
+use crate::tmpfile::TmpFile;
+use bytes::Bytes;
+use std::fs::{
+    self,
+    File,
+};
+use std::io;
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+/// A directory where downloaded artifacts are cached between runs.
+#[derive(Clone, Debug)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// Create a new [`Cache`] in the given `dir`. The directory is created
+    /// when the first artifact is stored.
+    #[must_use]
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir: dir,
+        }
+    }
+
+    /// Return the cache directory.
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Return the name that the zip for the given `product`, `version`, `os`
+    /// and `arch` is cached under.
+    #[must_use]
+    pub fn zip_name(product: &str, version: &str, os: &str, arch: &str) -> String {
+        format!("{product}_{version}_{os}_{arch}.zip")
+    }
+
+    /// Return the path that the artifact `name` is cached at.
+    #[must_use]
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    /// Load the cached artifact `name` into a new [`TmpFile`] named
+    /// `filename`. Returns `None` if the artifact isn't cached.
+    ///
+    /// The SHA256 of the artifact is calculated as it's copied, so checking
+    /// it doesn't need a second read, see [`TmpFile::sha256`].
+    ///
+    /// # Errors
+    ///
+    /// Errors if:
+    ///   - Failing to create the [`TmpFile`]
+    ///   - Failing to copy the cached artifact into the [`TmpFile`]
+    pub fn load(
+        &self,
+        name: &str,
+        filename: &str,
+    ) -> Result<Option<TmpFile>, ClientError> {
+        let path = self.path(name);
+
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let mut file    = File::open(&path)?;
+        let mut tmpfile = TmpFile::new(filename)?;
+        let mut writer  = Sha256Writer::new(tmpfile.handle()?);
+
+        io::copy(&mut file, &mut writer)?;
+
+        let sha256 = writer.hex_digest();
+        tmpfile.set_sha256(sha256);
+
+        Ok(Some(tmpfile))
+    }
+
+    /// Read the cached artifact `name`. Returns `None` if the artifact isn't
+    /// cached.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the cached artifact can't be read.
+    pub fn read(&self, name: &str) -> Result<Option<Bytes>, ClientError> {
+        let path = self.path(name);
+
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let content = fs::read(&path)?;
+
+        Ok(Some(Bytes::from(content)))
+    }
+
+    /// Store the content of `tmpfile` in the cache as `name`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if:
+    ///   - Failing to create the cache directory
+    ///   - Failing to write the cached artifact
+    pub fn store(
+        &self,
+        name: &str,
+        tmpfile: &mut TmpFile,
+    ) -> Result<(), ClientError> {
+        fs::create_dir_all(&self.dir)?;
+
+        tmpfile.persist_to(&self.path(name))?;
+
+        Ok(())
+    }
+
+    /// Write `content` to the cache as `name`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if:
+    ///   - Failing to create the cache directory
+    ///   - Failing to write the cached artifact
+    pub fn write(&self, name: &str, content: &[u8]) -> Result<(), ClientError> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.path(name), content)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::io::{
+        Read,
+        Write,
+    };
+
+    #[test]
+    fn test_zip_name() {
+        let name = Cache::zip_name("terraform", "1.5.7", "linux", "amd64");
+
+        assert_eq!(name, "terraform_1.5.7_linux_amd64.zip");
+    }
+
+    #[test]
+    fn test_load_miss() {
+        let dir   = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().to_path_buf());
+
+        let cached = cache.load("missing.zip", "missing.zip").unwrap();
+
+        assert!(cached.is_none());
+    }
+
+    #[test]
+    fn test_read_miss() {
+        let dir   = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().to_path_buf());
+
+        assert!(cache.read("missing.json").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_write_and_read() {
+        let dir   = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().join("cache"));
+
+        cache.write("test.json", b"{}").unwrap();
+
+        let content = cache.read("test.json").unwrap().unwrap();
+
+        assert_eq!(content, Bytes::from_static(b"{}"));
+    }
+
+    #[test]
+    fn test_store_and_load() {
+        let dir   = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().join("cache"));
+
+        let mut tmpfile = TmpFile::new("test.zip").unwrap();
+        tmpfile.handle().unwrap().write_all(b"cached content").unwrap();
+
+        cache.store("test.zip", &mut tmpfile).unwrap();
+
+        let mut cached = cache
+            .load("test.zip", "download.zip")
+            .unwrap()
+            .unwrap();
+
+        // The SHA256 is known without reading the content back.
+        assert_eq!(
+            cached.sha256(),
+            Some("0c486fdb66db23171c5b8b35a89ab000432e1090fa23fae283db5063ccac184a"),
+        );
+
+        let mut content = String::new();
+        cached.handle().unwrap().read_to_string(&mut content).unwrap();
+
+        assert_eq!(cached.filename(), "download.zip");
+        assert_eq!(content, "cached content");
+    }
+}
@@ -1,13 +1,352 @@
 // Client configuration
+use super::{
+    api_url,
+    proxy_from_env,
+    redacted,
+    request_headers,
+    HASHICORP_GPG_KEY_URL,
+    HTTPS_PROXY_ENV,
+    HTTP_PROXY_ENV,
+};
+use super::event::{
+    ClientEvent,
+    EventHandler,
+};
+use crate::error::ClientError;
+use indicatif::MultiProgress;
+use serde::Serialize;
+use std::fmt;
+use std::net::{
+    IpAddr,
+    Ipv4Addr,
+    Ipv6Addr,
+};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+// Default timeout for establishing a connection.
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Default timeout for receiving the next chunk of a download.
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Default limit on the size of downloads held in memory, 64 MiB.
+const DEFAULT_MAX_MEMORY_DOWNLOAD: u64 = 64 * 1024 * 1024;
+
+// Number of times a failed download will be retried by default.
+const DEFAULT_RETRIES: u32 = 3;
+
+// Initial delay between retries, this doubles with each retry.
+const DEFAULT_RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// [`IpFamily`] selects the IP address family used for connections.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IpFamily {
+    /// Connect over whichever family the resolver returns, the default.
+    #[default]
+    Auto,
+
+    /// Connect over IPv4 only, ignoring any IPv6 addresses.
+    V4Only,
+
+    /// Connect over IPv6 only, ignoring any IPv4 addresses.
+    V6Only,
+}
+
+impl IpFamily {
+    /// Returns the unspecified local address that connections are bound to
+    /// for this family. Binding to an address of one family restricts
+    /// connections to remote addresses of the same family. Returns `None`
+    /// for [`IpFamily::Auto`].
+    #[must_use]
+    pub fn local_address(self) -> Option<IpAddr> {
+        match self {
+            Self::Auto   => None,
+            Self::V4Only => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            Self::V6Only => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        }
+    }
+}
+
+/// [`RequestHeader`] is a header sent with every request made by the
+/// [`crate::client::Client`]. The value is never included in debug output, as
+/// it's likely to be a credential.
+#[derive(Clone, Eq, PartialEq)]
+pub struct RequestHeader {
+    /// The name of the header.
+    pub name: String,
+
+    /// The value of the header.
+    pub value: String,
+}
+
+impl fmt::Debug for RequestHeader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RequestHeader")
+            .field("name", &self.name)
+            .field("value", &"<redacted>")
+            .finish()
+    }
+}
 
 /// [`ClientConfig`] is a configuration for [`crate::client::Client`].
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct ClientConfig {
+    /// Accept invalid TLS certificates. This is dangerous and should only be
+    /// used for testing.
+    pub accept_invalid_certs: bool,
+
+    /// Base URL of the releases API. If unset, the `HCDL_API_URL`
+    /// environment variable is used, falling back to the HashiCorp releases
+    /// API.
+    pub api_url: Option<Url>,
+
+    /// Maximum number of bytes per second to download, shared between all
+    /// downloads made by the client. If unset, downloads aren't throttled.
+    pub bandwidth_limit: Option<u64>,
+
+    /// Directory where downloaded zips are cached. If unset, downloads are
+    /// not cached.
+    pub cache_dir: Option<PathBuf>,
+
+    /// Timeout for establishing a connection.
+    pub connect_timeout: Duration,
+
+    /// Number of connections a single download is split across, each
+    /// fetching a range of the content. Servers that don't support range
+    /// requests are downloaded from over a single connection. Defaults to 1.
+    pub download_connections: u8,
+
+    /// Request the enterprise (`+ent`) variants of products.
+    pub enterprise: bool,
+
+    /// Draw the download progress bars even when stderr isn't a terminal.
+    pub force_progress: bool,
+
+    /// Path to an armored GPG key to verify signatures against. If set, this
+    /// takes priority over the embedded or data directory key.
+    pub gpg_key_path: Option<PathBuf>,
+
+    /// URL to fetch the HashiCorp GPG key from. If unset, the key is fetched
+    /// from the HashiCorp well-known URL.
+    pub gpg_key_url: Option<Url>,
+
+    /// Request gzip compressed responses, transparently decompressing them.
+    /// Defaults to true.
+    pub gzip: bool,
+
+    /// Make a HEAD request before each download, to learn its size and
+    /// whether it can be resumed before starting.
+    pub head_precheck: bool,
+
+    /// Headers sent with requests to the releases API host, including
+    /// downloads from it, and to the `mirrors` if `mirror_headers` is set. If
+    /// no `Authorization` header is given, one with a bearer token is sent if
+    /// the `HCDL_API_TOKEN` environment variable is set.
+    pub headers: Vec<RequestHeader>,
+
+    /// Proxy to use for HTTP requests. If unset, the `HTTP_PROXY`
+    /// environment variable is used.
+    pub http_proxy: Option<Url>,
+
+    /// Proxy to use for HTTPS requests. If unset, the `HTTPS_PROXY`
+    /// environment variable is used.
+    pub https_proxy: Option<Url>,
+
+    /// IP address family used for connections. Defaults to
+    /// [`IpFamily::Auto`].
+    pub ip_family: IpFamily,
+
+    /// Consider pre-release versions, e.g. `1.6.0-rc1`, when finding the
+    /// latest version or resolving a version constraint.
+    pub include_prerelease: bool,
+
+    /// Maximum size of a download held in memory by
+    /// [`crate::client::Client::download_to_bytes`].
+    pub max_memory_download: u64,
+
+    /// Also send the configured `headers` to the `mirrors`.
+    pub mirror_headers: bool,
+
+    /// Base URLs of mirrors of the HashiCorp releases site, tried in order
+    /// when fetching from `releases.hashicorp.com` fails.
+    pub mirrors: Vec<Url>,
+
+    /// [`MultiProgress`] that download progress bars are registered with, so
+    /// that concurrent downloads don't draw over each other.
+    pub multi_progress: Option<Arc<MultiProgress>>,
+
     /// Control the output of colour in the crate messages and progress bars.
     pub no_color: bool,
 
-    /// Controls the output of text in the crate.
+    /// Forbid network access, all artifacts are read from the `cache_dir`
+    /// instead.
+    pub offline: bool,
+
+    /// Handler called for events emitted by the client.
+    pub on_event: Option<EventHandler>,
+
+    /// Hide the download progress bars, the only text output of the crate.
+    pub quiet: bool,
+
+    /// Timeout for receiving each chunk of a download. A stalled download
+    /// errors once this is exceeded.
+    pub read_timeout: Duration,
+
+    /// Overall timeout for requests, excluding downloads. Defaults to no
+    /// timeout.
+    pub request_timeout: Option<Duration>,
+
+    /// ID of the only GPG key that may have made the shasums signatures. If
+    /// unset, a signature made by any key in the trusted public key is
+    /// accepted.
+    pub require_key_id: Option<String>,
+
+    /// Number of times a download will be retried after a transient failure.
+    pub retries: u32,
+
+    /// Delay before the first retry of a download, doubling for each
+    /// subsequent retry.
+    pub retry_base_delay: Duration,
+
+    /// Paths to PEM files containing additional root certificates to trust.
+    pub root_certificates: Vec<PathBuf>,
+}
+
+/// [`EffectiveConfig`] is a serializable view of a [`ClientConfig`], with
+/// settings left to the environment or defaults resolved, and any credentials
+/// in URLs redacted.
+#[derive(Debug, Serialize)]
+pub struct EffectiveConfig {
+    /// Whether invalid TLS certificates are accepted.
+    pub accept_invalid_certs: bool,
+
+    /// Base URL of the releases API.
+    pub api_url: String,
+
+    /// Maximum number of bytes per second to download.
+    pub bandwidth_limit: Option<u64>,
+
+    /// Directory where downloaded zips are cached.
+    pub cache_dir: Option<PathBuf>,
+
+    /// Timeout for establishing a connection, in seconds.
+    pub connect_timeout_secs: f64,
+
+    /// Number of connections a single download is split across.
+    pub download_connections: u8,
+
+    /// Whether the enterprise variants of products are requested.
+    pub enterprise: bool,
+
+    /// Whether progress bars are drawn when stderr isn't a terminal.
+    pub force_progress: bool,
+
+    /// Path to the GPG key signatures are verified against, if not the
+    /// embedded or data directory key.
+    pub gpg_key_path: Option<PathBuf>,
+
+    /// URL the HashiCorp GPG key is fetched from.
+    pub gpg_key_url: Url,
+
+    /// Whether gzip compressed responses are requested.
+    pub gzip: bool,
+
+    /// Whether a HEAD request is made before each download.
+    pub head_precheck: bool,
+
+    /// Names of the headers sent with every request, their values are
+    /// redacted.
+    pub headers: Vec<String>,
+
+    /// Proxy used for HTTP requests.
+    pub http_proxy: Option<Url>,
+
+    /// Proxy used for HTTPS requests.
+    pub https_proxy: Option<Url>,
+
+    /// Whether pre-release versions are considered.
+    pub include_prerelease: bool,
+
+    /// IP address family used for connections.
+    pub ip_family: IpFamily,
+
+    /// Maximum size of a download held in memory, in bytes.
+    pub max_memory_download: u64,
+
+    /// Whether the configured headers are also sent to the mirrors.
+    pub mirror_headers: bool,
+
+    /// Base URLs of mirrors of the HashiCorp releases site.
+    pub mirrors: Vec<Url>,
+
+    /// Whether colour output is disabled.
+    pub no_color: bool,
+
+    /// Whether network access is forbidden.
+    pub offline: bool,
+
+    /// Whether text output is silenced.
     pub quiet: bool,
+
+    /// Timeout for receiving each chunk of a download, in seconds.
+    pub read_timeout_secs: f64,
+
+    /// Overall timeout for requests, in seconds.
+    pub request_timeout_secs: Option<f64>,
+
+    /// ID of the only GPG key that may have made the shasums signatures.
+    pub require_key_id: Option<String>,
+
+    /// Number of times a download is retried after a transient failure.
+    pub retries: u32,
+
+    /// Delay before the first retry of a download, in seconds.
+    pub retry_base_delay_secs: f64,
+
+    /// Paths to PEM files containing additional root certificates.
+    pub root_certificates: Vec<PathBuf>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            accept_invalid_certs: false,
+            api_url:              None,
+            bandwidth_limit:      None,
+            cache_dir:            None,
+            connect_timeout:      DEFAULT_CONNECT_TIMEOUT,
+            download_connections: 1,
+            enterprise:           false,
+            force_progress:       false,
+            gpg_key_path:         None,
+            gpg_key_url:          None,
+            gzip:                 true,
+            head_precheck:        false,
+            headers:              Vec::new(),
+            http_proxy:           None,
+            https_proxy:          None,
+            include_prerelease:   false,
+            ip_family:            IpFamily::Auto,
+            max_memory_download:  DEFAULT_MAX_MEMORY_DOWNLOAD,
+            mirror_headers:       false,
+            mirrors:              Vec::new(),
+            multi_progress:       None,
+            no_color:             false,
+            offline:              false,
+            on_event:             None,
+            quiet:                false,
+            read_timeout:         DEFAULT_READ_TIMEOUT,
+            request_timeout:      None,
+            require_key_id:       None,
+            retries:              DEFAULT_RETRIES,
+            retry_base_delay:     DEFAULT_RETRY_BASE_DELAY,
+            root_certificates:    Vec::new(),
+        }
+    }
 }
 
 impl ClientConfig {
@@ -17,6 +356,280 @@ impl ClientConfig {
         Self::default()
     }
 
+    /// `effective` returns the [`EffectiveConfig`] that a
+    /// [`crate::client::Client`] built from this configuration would use.
+    /// The API URL and proxies fall back to the environment, as they do for
+    /// the client, and credentials in any of the URLs are redacted.
+    ///
+    /// # Errors
+    ///
+    /// Errors if:
+    ///   - The API URL isn't a valid http(s) URL
+    ///   - A proxy from the environment isn't a valid URL
+    ///   - A header name or value isn't valid
+    pub fn effective(&self) -> Result<EffectiveConfig, ClientError> {
+        let http_proxy = match &self.http_proxy {
+            Some(url) => Some(url.clone()),
+            None      => proxy_from_env(HTTP_PROXY_ENV)?,
+        };
+
+        let https_proxy = match &self.https_proxy {
+            Some(url) => Some(url.clone()),
+            None      => proxy_from_env(HTTPS_PROXY_ENV)?,
+        };
+
+        let gpg_key_url = match &self.gpg_key_url {
+            Some(url) => url.clone(),
+            None      => Url::parse(HASHICORP_GPG_KEY_URL)
+                .map_err(|_err| ClientError::Url("effective"))?,
+        };
+
+        // The API URL is shown as it's used, apart from any credentials.
+        let api_url = api_url(self)?;
+        let api_url = Url::parse(&api_url)
+            .map_err(|_err| ClientError::ApiUrl(api_url.clone()))?;
+        let api_url = redacted(&api_url)
+            .as_str()
+            .trim_end_matches('/')
+            .to_string();
+
+        let headers = request_headers(self)?
+            .keys()
+            .map(ToString::to_string)
+            .collect();
+
+        let effective = EffectiveConfig {
+            accept_invalid_certs:  self.accept_invalid_certs,
+            api_url:               api_url,
+            bandwidth_limit:       self.bandwidth_limit,
+            cache_dir:             self.cache_dir.clone(),
+            connect_timeout_secs:  self.connect_timeout.as_secs_f64(),
+            download_connections:  self.download_connections,
+            enterprise:            self.enterprise,
+            force_progress:        self.force_progress,
+            gpg_key_path:          self.gpg_key_path.clone(),
+            gpg_key_url:           redacted(&gpg_key_url),
+            gzip:                  self.gzip,
+            head_precheck:         self.head_precheck,
+            headers:               headers,
+            http_proxy:            http_proxy.as_ref().map(redacted),
+            https_proxy:           https_proxy.as_ref().map(redacted),
+            include_prerelease:    self.include_prerelease,
+            ip_family:             self.ip_family,
+            max_memory_download:   self.max_memory_download,
+            mirror_headers:        self.mirror_headers,
+            mirrors:               self.mirrors.iter().map(redacted).collect(),
+            no_color:              self.no_color,
+            offline:               self.offline,
+            quiet:                 self.quiet,
+            read_timeout_secs:     self.read_timeout.as_secs_f64(),
+            request_timeout_secs:  self.request_timeout
+                .as_ref()
+                .map(Duration::as_secs_f64),
+            require_key_id:        self.require_key_id.clone(),
+            retries:               self.retries,
+            retry_base_delay_secs: self.retry_base_delay.as_secs_f64(),
+            root_certificates:     self.root_certificates.clone(),
+        };
+
+        Ok(effective)
+    }
+
+    /// `add_root_certificate` adds the certificates in the PEM file at `path`
+    /// to the trusted root certificates. May be called multiple times to add
+    /// multiple files.
+    #[must_use]
+    pub fn add_root_certificate(mut self, path: PathBuf) -> Self {
+        self.root_certificates.push(path);
+        self
+    }
+
+    /// `api_url` sets the base URL of the releases API, allowing a mirror of
+    /// the HashiCorp releases API to be used.
+    #[must_use]
+    pub fn api_url(mut self, url: Url) -> Self {
+        self.api_url = Some(url);
+        self
+    }
+
+    /// `bandwidth_limit` caps the number of bytes per second downloaded,
+    /// shared between all downloads made by the client. `None` removes the
+    /// cap.
+    #[must_use]
+    pub fn bandwidth_limit(mut self, limit: Option<u64>) -> Self {
+        self.bandwidth_limit = limit;
+        self
+    }
+
+    /// `cache_dir` sets the directory where downloaded zips are cached.
+    #[must_use]
+    pub fn cache_dir(mut self, dir: PathBuf) -> Self {
+        self.cache_dir = Some(dir);
+        self
+    }
+
+    /// `connect_timeout` controls how long to wait while establishing a
+    /// connection.
+    #[must_use]
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// `danger_accept_invalid_certs` disables TLS certificate validation.
+    /// This is dangerous and allows any certificate to be trusted, it should
+    /// only be used for testing.
+    #[must_use]
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// `download_connections` sets the number of connections a single
+    /// download is split across, for servers that support range requests.
+    /// A value of 0 is treated as 1.
+    #[must_use]
+    pub fn download_connections(mut self, connections: u8) -> Self {
+        self.download_connections = connections.max(1);
+        self
+    }
+
+    /// `enterprise` requests the enterprise (`+ent`) variants of products,
+    /// for products that have them.
+    #[must_use]
+    pub fn enterprise(mut self, enterprise: bool) -> Self {
+        self.enterprise = enterprise;
+        self
+    }
+
+    /// `force_progress` draws the download progress bars even when stderr
+    /// isn't a terminal, where they're hidden by default. `quiet` still
+    /// takes priority.
+    #[must_use]
+    pub fn force_progress(mut self, force: bool) -> Self {
+        self.force_progress = force;
+        self
+    }
+
+    /// `gpg_key_path` sets the path of an armored GPG key that signatures are
+    /// verified against, instead of the embedded or data directory key.
+    #[must_use]
+    pub fn gpg_key_path(mut self, path: PathBuf) -> Self {
+        self.gpg_key_path = Some(path);
+        self
+    }
+
+    /// `gpg_key_url` sets the URL that the HashiCorp GPG key is fetched from.
+    #[must_use]
+    pub fn gpg_key_url(mut self, url: Url) -> Self {
+        self.gpg_key_url = Some(url);
+        self
+    }
+
+    /// `gzip` controls whether gzip compressed responses are requested and
+    /// transparently decompressed. Disabling this can help with proxies that
+    /// mishandle gzip.
+    #[must_use]
+    pub fn gzip(mut self, gzip: bool) -> Self {
+        self.gzip = gzip;
+        self
+    }
+
+    /// `head_precheck` makes a HEAD request before each download, so its
+    /// size and support for resuming are known before it starts. Servers
+    /// that reject HEAD requests fall back to learning the size from the
+    /// download itself.
+    #[must_use]
+    pub fn head_precheck(mut self, head_precheck: bool) -> Self {
+        self.head_precheck = head_precheck;
+        self
+    }
+
+    /// `header` adds a header to be sent with requests to the releases API
+    /// host, including downloads from it, such as the `Authorization` header
+    /// needed by some mirrors of the API. Headers are only sent to the
+    /// `mirrors` if enabled with `mirror_headers`.
+    /// May be called multiple times to add multiple headers, or multiple
+    /// values of the same header. Names and values are validated when the
+    /// [`crate::client::Client`] is created.
+    #[must_use]
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push(RequestHeader {
+            name:  name.to_string(),
+            value: value.to_string(),
+        });
+        self
+    }
+
+    /// `http_proxy` sets the proxy used for HTTP requests. Credentials
+    /// embedded in the `url` are used to authenticate with the proxy.
+    #[must_use]
+    pub fn http_proxy(mut self, url: Url) -> Self {
+        self.http_proxy = Some(url);
+        self
+    }
+
+    /// `https_proxy` sets the proxy used for HTTPS requests. Credentials
+    /// embedded in the `url` are used to authenticate with the proxy.
+    #[must_use]
+    pub fn https_proxy(mut self, url: Url) -> Self {
+        self.https_proxy = Some(url);
+        self
+    }
+
+    /// `include_prerelease` controls whether pre-release versions, e.g.
+    /// `1.6.0-rc1`, are considered when finding the latest version or
+    /// resolving a version constraint. Pre-releases are skipped by default.
+    #[must_use]
+    pub fn include_prerelease(mut self, include: bool) -> Self {
+        self.include_prerelease = include;
+        self
+    }
+
+    /// `ip_family` restricts connections to IPv4 or IPv6 addresses. This is
+    /// useful on dual-stack networks where one family is broken, e.g. where
+    /// IPv6 addresses resolve but IPv6 traffic can't leave the network.
+    #[must_use]
+    pub fn ip_family(mut self, family: IpFamily) -> Self {
+        self.ip_family = family;
+        self
+    }
+
+    /// `max_memory_download` sets the maximum size, in bytes, of a download
+    /// held in memory by [`crate::client::Client::download_to_bytes`].
+    #[must_use]
+    pub fn max_memory_download(mut self, max: u64) -> Self {
+        self.max_memory_download = max;
+        self
+    }
+
+    /// `mirror_headers` controls whether the configured headers are also sent
+    /// to the `mirrors`. This is off by default, as the headers usually
+    /// carry a credential for the releases API.
+    #[must_use]
+    pub fn mirror_headers(mut self, mirror_headers: bool) -> Self {
+        self.mirror_headers = mirror_headers;
+        self
+    }
+
+    /// `mirrors` sets the base URLs of mirrors of the HashiCorp releases
+    /// site. When a download, shasums or signature fetch from
+    /// `releases.hashicorp.com` fails, the URL path is tried under each
+    /// mirror in turn.
+    #[must_use]
+    pub fn mirrors(mut self, mirrors: Vec<Url>) -> Self {
+        self.mirrors = mirrors;
+        self
+    }
+
+    /// `multi_progress` registers the download progress bars with the given
+    /// [`MultiProgress`], for callers making several downloads at once.
+    #[must_use]
+    pub fn multi_progress(mut self, multi: Arc<MultiProgress>) -> Self {
+        self.multi_progress = Some(multi);
+        self
+    }
+
     /// `no_color` controls the output of colours in the various output of the
     /// crate.
     #[must_use]
@@ -25,10 +638,72 @@ impl ClientConfig {
         self
     }
 
-    /// `quiet` controls the various text output of the crate.
+    /// `offline` forbids network access. Artifacts are read from the
+    /// `cache_dir` instead, which must have been populated by a previous
+    /// online run or by hand.
+    #[must_use]
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// `on_event` sets a handler that is called for each [`ClientEvent`]
+    /// emitted by the client.
+    #[must_use]
+    pub fn on_event<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&ClientEvent) + Send + Sync + 'static,
+    {
+        self.on_event = Some(EventHandler::new(handler));
+        self
+    }
+
+    /// `quiet` hides the download progress bars, the only text output of the
+    /// crate.
     #[must_use]
     pub fn quiet(mut self, quiet: bool) -> Self {
         self.quiet = quiet;
         self
     }
+
+    /// `read_timeout` controls how long to wait for each chunk of a download
+    /// before giving up.
+    #[must_use]
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// `request_timeout` controls the overall timeout for requests. This
+    /// doesn't apply to downloads, which may legitimately take a long time.
+    #[must_use]
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// `require_key_id` restricts the shasums signatures accepted to those
+    /// made by the GPG key with the given short or long `key_id`, or
+    /// fingerprint.
+    #[must_use]
+    pub fn require_key_id(mut self, key_id: String) -> Self {
+        self.require_key_id = Some(key_id);
+        self
+    }
+
+    /// `retries` controls how many times a download is retried after a
+    /// transient failure.
+    #[must_use]
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// `retry_base_delay` controls the delay before the first retry of a
+    /// download. The delay doubles for each subsequent retry.
+    #[must_use]
+    pub fn retry_base_delay(mut self, delay: Duration) -> Self {
+        self.retry_base_delay = delay;
+        self
+    }
 }
@@ -0,0 +1,111 @@
+// constraint: Parse version constraints
+#![forbid(unsafe_code)]
+#![forbid(missing_docs)]
+use semver::VersionReq;
+
+// The pessimistic constraint operator used by HashiCorp tools.
+const PESSIMISTIC_OPERATOR: &str = "~>";
+
+/// Parse a version `constraint` into a [`VersionReq`].
+///
+/// In addition to the constraints understood by [`semver`], the pessimistic
+/// `~>` operator used by HashiCorp tools is accepted. `~> 1.5` allows any
+/// version from `1.5.0` up to, but not including, `2.0.0`, while `~> 1.5.7`
+/// allows any version from `1.5.7` up to, but not including, `1.6.0`.
+///
+/// # Errors
+///
+/// Errors if the `constraint` can't be parsed.
+///
+/// # Examples
+///
+/// ```
+/// use hcdl::client::constraint;
+/// use semver::Version;
+///
+/// let constraint = constraint::parse("~> 1.5").unwrap();
+///
+/// assert!(constraint.matches(&Version::parse("1.6.2").unwrap()));
+/// assert!(!constraint.matches(&Version::parse("2.0.0").unwrap()));
+/// ```
+pub fn parse(constraint: &str) -> Result<VersionReq, semver::Error> {
+    let constraint = constraint
+        .split(',')
+        .map(|comparator| {
+            let comparator = comparator.trim();
+
+            match comparator.strip_prefix(PESSIMISTIC_OPERATOR) {
+                Some(version) => pessimistic(version.trim()),
+                None          => comparator.to_string(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    VersionReq::parse(&constraint)
+}
+
+// Convert the version from a pessimistic constraint into the equivalent
+// semver comparators.
+fn pessimistic(version: &str) -> String {
+    let parts: Vec<&str> = version.split('.').collect();
+
+    // With only a major or major.minor version, the rightmost component may
+    // increase, which means everything up to the next major version.
+    if parts.len() < 3 {
+        if let Ok(major) = parts[0].parse::<u64>() {
+            return format!(">={version}, <{next}", next = major + 1);
+        }
+    }
+
+    // With a full version, only the patch version may increase, which is
+    // what the semver tilde operator gives us. This also lets an invalid
+    // version fail to parse in the usual way.
+    format!("~{version}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use semver::Version;
+
+    fn matches(constraint: &str, version: &str) -> bool {
+        let constraint = parse(constraint).unwrap();
+        let version    = Version::parse(version).unwrap();
+
+        constraint.matches(&version)
+    }
+
+    #[test]
+    fn test_parse_pessimistic_major_minor() {
+        assert!(matches("~> 1.5", "1.5.0"));
+        assert!(matches("~> 1.5", "1.9.3"));
+        assert!(!matches("~> 1.5", "1.4.9"));
+        assert!(!matches("~> 1.5", "2.0.0"));
+    }
+
+    #[test]
+    fn test_parse_pessimistic_full_version() {
+        assert!(matches("~> 1.5.7", "1.5.7"));
+        assert!(matches("~> 1.5.7", "1.5.9"));
+        assert!(!matches("~> 1.5.7", "1.6.0"));
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert!(matches(">=1.6, <2.0", "1.6.0"));
+        assert!(!matches(">=1.6, <2.0", "1.5.7"));
+        assert!(!matches(">=1.6, <2.0", "2.0.0"));
+    }
+
+    #[test]
+    fn test_parse_excludes_prerelease() {
+        assert!(!matches(">=1.5", "1.6.0-rc1"));
+        assert!(matches(">=1.6.0-rc1", "1.6.0-rc1"));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse("~> nope").is_err());
+    }
+}
@@ -0,0 +1,71 @@
+// Events emitted by the client
+#![forbid(unsafe_code)]
+#![forbid(missing_docs)]
+use crate::error::ClientError;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+/// Events emitted by the [`crate::client::Client`] while it is working, so
+/// that callers can report progress to users.
+#[derive(Debug)]
+pub enum ClientEvent<'a> {
+    /// Emitted when the HashiCorp GPG key has been fetched and stored.
+    GpgKeyFetched {
+        /// The fingerprint of the fetched key.
+        fingerprint: &'a str,
+
+        /// The path the key was stored at.
+        path: &'a Path,
+    },
+
+    /// Emitted when content was fetched from a mirror, after fetching it
+    /// from `releases.hashicorp.com` and any earlier mirrors failed.
+    Mirror {
+        /// The URL the content was fetched from.
+        url: &'a Url,
+    },
+
+    /// Emitted when a download failed with a transient error and is about to
+    /// be retried.
+    Retry {
+        /// The retry attempt number, starting at 1.
+        attempt: u32,
+
+        /// The maximum number of retries that will be attempted.
+        retries: u32,
+
+        /// How long the client will wait before retrying.
+        delay: Duration,
+
+        /// The error that caused the retry.
+        error: &'a ClientError,
+    },
+}
+
+/// A handler that is called for each [`ClientEvent`].
+#[derive(Clone)]
+pub struct EventHandler(Arc<dyn Fn(&ClientEvent) + Send + Sync>);
+
+impl EventHandler {
+    /// Create a new [`EventHandler`] from the given function.
+    pub fn new<F>(handler: F) -> Self
+    where
+        F: Fn(&ClientEvent) + Send + Sync + 'static,
+    {
+        Self(Arc::new(handler))
+    }
+
+    /// Call the handler with the given `event`.
+    pub fn emit(&self, event: &ClientEvent) {
+        (self.0)(event);
+    }
+}
+
+impl fmt::Debug for EventHandler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("EventHandler")
+    }
+}
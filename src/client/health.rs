@@ -0,0 +1,151 @@
+// health: Results of the client health check
+#![forbid(unsafe_code)]
+#![forbid(missing_docs)]
+use serde::Serialize;
+use std::fmt;
+
+/// A step of the health check run by [`crate::client::Client::healthcheck`],
+/// in the order they're run.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStep {
+    /// Reaching the releases API and parsing the latest version of the
+    /// well-known product.
+    Api,
+
+    /// Fetching the shasums and signatures of the latest version and
+    /// verifying them against the GPG key.
+    Signature,
+
+    /// Writing a file to the install directory.
+    InstallDir,
+}
+
+impl fmt::Display for HealthStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let step = match self {
+            Self::Api        => "releases API",
+            Self::Signature  => "signature verification",
+            Self::InstallDir => "install directory",
+        };
+
+        write!(f, "{step}")
+    }
+}
+
+/// The outcome of a [`HealthStep`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "status", content = "error")]
+pub enum HealthStatus {
+    /// The step passed.
+    Passed,
+
+    /// The step failed, with the error it failed with.
+    Failed(String),
+
+    /// The step wasn't run, as a step it depends on failed.
+    Skipped,
+}
+
+/// The outcome of a single [`HealthStep`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct HealthCheck {
+    /// The step that was checked.
+    pub step: HealthStep,
+
+    /// The outcome of the step.
+    #[serde(flatten)]
+    pub status: HealthStatus,
+}
+
+/// The report of a health check, with the outcome of every [`HealthStep`].
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct HealthReport {
+    /// The product the health check was run against.
+    pub product: String,
+
+    /// The version of the product the health check was run against, if it
+    /// could be found.
+    pub version: Option<String>,
+
+    /// The outcome of each step, in the order they were run.
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    /// Returns `true` if every step passed.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|check| check.status == HealthStatus::Passed)
+    }
+
+    // Record the outcome of `step`.
+    pub(crate) fn record<E>(&mut self, step: HealthStep, result: Result<(), E>)
+    where
+        E: fmt::Display,
+    {
+        let status = match result {
+            Ok(())   => HealthStatus::Passed,
+            Err(err) => HealthStatus::Failed(err.to_string()),
+        };
+
+        self.checks.push(HealthCheck {
+            step:   step,
+            status: status,
+        });
+    }
+
+    // Record `step` as skipped.
+    pub(crate) fn skip(&mut self, step: HealthStep) {
+        self.checks.push(HealthCheck {
+            step:   step,
+            status: HealthStatus::Skipped,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_passed() {
+        let mut report = HealthReport::default();
+
+        report.record::<String>(HealthStep::Api, Ok(()));
+
+        assert!(report.passed());
+
+        report.skip(HealthStep::Signature);
+
+        assert!(!report.passed());
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut report = HealthReport {
+            product: "terraform".into(),
+            ..HealthReport::default()
+        };
+
+        report.record::<String>(HealthStep::Api, Ok(()));
+        report.record(HealthStep::Signature, Err("bad signature"));
+        report.skip(HealthStep::InstallDir);
+
+        let expected = json!({
+            "product": "terraform",
+            "version": null,
+            "checks":  [
+                {"step": "api", "status": "passed"},
+                {"step": "signature", "status": "failed", "error": "bad signature"},
+                {"step": "install_dir", "status": "skipped"},
+            ],
+        });
+
+        assert_eq!(json!(report), expected);
+    }
+}
@@ -5,6 +5,7 @@ use chrono::{
     DateTime,
     Utc,
 };
+use semver::Version;
 use serde::{
     de,
     Deserialize,
@@ -16,6 +17,16 @@ use url::Url;
 
 pub use super::build::Build;
 
+// The arch used when a build for the requested arch isn't available, on the
+// OSes that can run it under emulation, e.g. Rosetta on macOS.
+const FALLBACK_ARCH: &str = "amd64";
+const FALLBACK_OS: &[&str] = &["darwin", "windows"];
+
+// Arches of universal builds, which run on every arch of the OS they're built
+// for. Only macOS has universal binaries.
+const UNIVERSAL_ARCH: &[&str] = &["universal", "all"];
+const UNIVERSAL_OS: &str = "darwin";
+
 /// Represents a single version of a [HashiCorp](https://hashicorp.io) product.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 pub struct ProductVersion {
@@ -55,6 +66,9 @@ where
 
 impl ProductVersion {
     /// Pull a specific [`Build`] out of the [`ProductVersion`] `builds`.
+    ///
+    /// On `darwin`, a universal build, with an arch of `universal` or `all`,
+    /// matches any `arch` when there's no build for that exact `arch`.
     #[must_use]
     pub fn build(&self, arch: &str, os: &str) -> Option<&Build> {
         let filtered: Vec<&Build> = self.builds
@@ -62,23 +76,53 @@ impl ProductVersion {
             .filter(|b| b.arch == arch && b.os == os)
             .collect();
 
-        if filtered.is_empty() {
-            None
+        if let Some(build) = filtered.first() {
+            return Some(build);
         }
-        else {
-            Some(filtered[0])
+
+        if os != UNIVERSAL_OS {
+            return None;
         }
+
+        self.builds
+            .iter()
+            .find(|b| b.os == os && UNIVERSAL_ARCH.contains(&b.arch.as_str()))
     }
 
-    /// Create and return the shasums signature URL.
-    ///
-    /// # Panics
-    ///
-    /// This function could panic if there are no signature URLs returned by
-    /// the API.
+    /// Pull a specific [`Build`] out of the [`ProductVersion`] `builds`,
+    /// falling back to an `amd64` build when there's no build for `arch` and
+    /// the `os` can run `amd64` builds under emulation. The returned `bool`
+    /// is `true` if the fallback build was chosen.
+    #[must_use]
+    pub fn build_with_fallback(
+        &self,
+        arch: &str,
+        os: &str,
+    ) -> Option<(&Build, bool)> {
+        if let Some(build) = self.build(arch, os) {
+            return Some((build, false));
+        }
+
+        if arch == FALLBACK_ARCH || !FALLBACK_OS.contains(&os) {
+            return None;
+        }
+
+        self.build(FALLBACK_ARCH, os).map(|build| (build, true))
+    }
+
+    /// Returns `true` if the `version` is a pre-release, e.g. `1.6.0-rc1`.
+    /// Versions that aren't valid semver are never pre-releases.
     #[must_use]
-    pub fn shasums_signature_url(&self) -> Url {
-        self.url_shasums_signatures.first().unwrap().clone()
+    pub fn is_prerelease(&self) -> bool {
+        Version::parse(&self.version)
+            .is_ok_and(|version| !version.pre.is_empty())
+    }
+
+    /// Return the first shasums signature URL, or `None` if the product
+    /// version doesn't publish any signatures.
+    #[must_use]
+    pub fn shasums_signature_url(&self) -> Option<Url> {
+        self.url_shasums_signatures.first().cloned()
     }
 
     /// Create and return the shasums URL.
@@ -141,6 +185,117 @@ mod tests {
         assert_eq!(build, &expected)
     }
 
+    #[test]
+    fn test_build_universal() {
+        let version = ProductVersion {
+            name:              "terraform".into(),
+            timestamp_created: DateTime::<Utc>::from_str("2024-01-10T16:55:35.000Z").unwrap(),
+            timestamp_updated: DateTime::<Utc>::from_str("2024-01-10T16:55:35.000Z").unwrap(),
+            url_shasums:       Url::parse("https://test.example.org/terraform_1.7.0_SHA256SUMS").unwrap(),
+            version:           "1.7.0".into(),
+            builds:            vec![
+                Build {
+                    arch: "universal".into(),
+                    os:   "darwin".into(),
+                    url:  Url::parse("https://test.example.org/terraform_1.7.0_darwin_universal.zip").unwrap(),
+                },
+                Build {
+                    arch: "amd64".into(),
+                    os:   "linux".into(),
+                    url:  Url::parse("https://test.example.org/terraform_1.7.0_linux_amd64.zip").unwrap(),
+                },
+            ],
+            url_shasums_signatures: vec![
+                Url::parse("https://test.example.org/terraform_1.7.0_SHA256SUMS.sig").unwrap(),
+            ],
+        };
+
+        for arch in ["amd64", "arm64", "universal"] {
+            let build = version.build(arch, "darwin").unwrap();
+
+            assert_eq!(build.arch, "universal", "{arch}");
+        }
+
+        // A universal build is used as is, it's never a fallback.
+        let (build, fallback) = version.build_with_fallback("arm64", "darwin").unwrap();
+
+        assert_eq!(build.arch, "universal");
+        assert!(!fallback);
+
+        // Only macOS has universal builds.
+        assert!(version.build("arm64", "linux").is_none());
+    }
+
+    #[test]
+    fn test_is_prerelease() {
+        let mut version = ProductVersion {
+            name:                   "terraform".into(),
+            timestamp_created:      DateTime::<Utc>::from_str("2023-10-04T16:55:35.000Z").unwrap(),
+            timestamp_updated:      DateTime::<Utc>::from_str("2023-10-04T16:55:35.000Z").unwrap(),
+            url_shasums:            Url::parse("https://test.example.org/terraform_1.6.0_SHA256SUMS").unwrap(),
+            version:                "1.6.0".into(),
+            builds:                 vec![],
+            url_shasums_signatures: vec![],
+        };
+
+        assert!(!version.is_prerelease());
+
+        for prerelease in ["1.6.0-rc1", "1.6.0-beta2", "1.6.0-alpha20230816"] {
+            version.version = prerelease.into();
+
+            assert!(version.is_prerelease(), "{prerelease}");
+        }
+
+        // Build metadata doesn't make a pre-release.
+        version.version = "1.15.0+ent".into();
+        assert!(!version.is_prerelease());
+
+        version.version = "1.15.0-rc1+ent".into();
+        assert!(version.is_prerelease());
+
+        version.version = "not semver".into();
+        assert!(!version.is_prerelease());
+    }
+
+    #[test]
+    fn test_build_with_fallback() {
+        let version = ProductVersion {
+            name:              "terraform".into(),
+            timestamp_created: DateTime::<Utc>::from_str("2020-05-27T16:55:35.000Z").unwrap(),
+            timestamp_updated: DateTime::<Utc>::from_str("2020-05-27T16:55:35.000Z").unwrap(),
+            url_shasums:       Url::parse("https://test.example.org/terraform_0.12.26_SHA256SUMS").unwrap(),
+            version:           "0.12.26".into(),
+            builds:            vec![
+                Build {
+                    arch: "amd64".into(),
+                    os:   "darwin".into(),
+                    url:  Url::parse("https://test.example.org/terraform_0.12.26_darwin_amd64.zip").unwrap(),
+                },
+                Build {
+                    arch: "amd64".into(),
+                    os:   "linux".into(),
+                    url:  Url::parse("https://test.example.org/terraform_0.12.26_linux_amd64.zip").unwrap(),
+                },
+            ],
+            url_shasums_signatures: vec![
+                Url::parse("https://test.example.org/terraform_0.12.26_SHA256SUMS.sig").unwrap(),
+            ],
+        };
+
+        let (build, fallback) = version.build_with_fallback("arm64", "darwin").unwrap();
+
+        assert_eq!(build.arch, "amd64");
+        assert!(fallback);
+
+        let (build, fallback) = version.build_with_fallback("amd64", "darwin").unwrap();
+
+        assert_eq!(build.arch, "amd64");
+        assert!(!fallback);
+
+        // Linux can't run amd64 builds on arm64.
+        assert!(version.build_with_fallback("arm64", "linux").is_none());
+    }
+
     #[test]
     fn test_shasums_url() {
         let version = ProductVersion {
@@ -178,6 +333,21 @@ mod tests {
         let expected = Url::parse("https://releases.hashicorp.com/terraform/0.12.26/terraform_0.12.26_SHA256SUMS.sig").unwrap();
         let url      = version.shasums_signature_url();
 
-        assert_eq!(url, expected)
+        assert_eq!(url, Some(expected))
+    }
+
+    #[test]
+    fn test_shasums_signature_url_none() {
+        let version = ProductVersion {
+            builds:                 vec![],
+            name:                   "terraform".into(),
+            timestamp_created:      DateTime::<Utc>::from_str("2017-04-05T16:55:35.000Z").unwrap(),
+            timestamp_updated:      DateTime::<Utc>::from_str("2017-04-05T16:55:35.000Z").unwrap(),
+            url_shasums:            Url::parse("https://releases.hashicorp.com/terraform/0.9.2/terraform_0.9.2_SHA256SUMS").unwrap(),
+            version:                "0.9.2".into(),
+            url_shasums_signatures: vec![],
+        };
+
+        assert_eq!(version.shasums_signature_url(), None)
     }
 }
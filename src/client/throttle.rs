@@ -0,0 +1,149 @@
+// throttle: Limit the bandwidth used by downloads
+#![forbid(unsafe_code)]
+#![forbid(missing_docs)]
+use std::io::{
+    self,
+    Write,
+};
+use std::sync::{
+    Arc,
+    Mutex,
+    PoisonError,
+};
+use std::time::{
+    Duration,
+    Instant,
+};
+
+/// A token bucket allowing `rate` bytes per second, with bursts of up to one
+/// second's worth of bytes.
+///
+/// The bucket starts empty, so even the first bytes are paced.
+#[derive(Debug)]
+pub struct TokenBucket {
+    last:   Instant,
+    rate:   u64,
+    tokens: f64,
+}
+
+impl TokenBucket {
+    /// Create a new [`TokenBucket`] allowing `rate` bytes per second.
+    #[must_use]
+    pub fn new(rate: u64) -> Self {
+        Self {
+            last:   Instant::now(),
+            rate:   rate.max(1),
+            tokens: 0.0,
+        }
+    }
+
+    /// Take `bytes` from the bucket, returning how long to wait before more
+    /// bytes may be transferred.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn consume(&mut self, bytes: usize) -> Duration {
+        let now  = Instant::now();
+        let rate = self.rate as f64;
+
+        let refill = now.duration_since(self.last).as_secs_f64() * rate;
+
+        self.last   = now;
+        self.tokens = (self.tokens + refill).min(rate) - bytes as f64;
+
+        if self.tokens < 0.0 {
+            Duration::from_secs_f64(-self.tokens / rate)
+        }
+        else {
+            Duration::ZERO
+        }
+    }
+}
+
+/// A writer that accounts for the bytes written to it against a shared
+/// [`TokenBucket`], if there is one.
+///
+/// Writes are never blocked, instead the delay owed is tracked and should be
+/// waited out by the caller, see [`Throttle::take_delay`].
+pub struct Throttle<W> {
+    bucket: Option<Arc<Mutex<TokenBucket>>>,
+    delay:  Duration,
+    inner:  W,
+}
+
+impl<W: Write> Throttle<W> {
+    /// Wrap `inner`, accounting writes against `bucket`. Without a `bucket`
+    /// writes are never delayed.
+    #[must_use]
+    pub fn new(inner: W, bucket: Option<Arc<Mutex<TokenBucket>>>) -> Self {
+        Self {
+            bucket: bucket,
+            delay:  Duration::ZERO,
+            inner:  inner,
+        }
+    }
+
+    /// Return the delay owed by the writes so far, resetting it.
+    pub fn take_delay(&mut self) -> Duration {
+        std::mem::take(&mut self.delay)
+    }
+}
+
+impl<W: Write> Write for Throttle<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+
+        if let Some(bucket) = &self.bucket {
+            // A poisoned lock only means another download panicked, the
+            // bucket itself is still usable.
+            let mut bucket = bucket
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+
+            // Only the latest delay matters, it already accounts for any
+            // previous deficit.
+            self.delay = bucket.consume(written);
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_consume() {
+        let mut bucket = TokenBucket::new(1000);
+
+        let delay = bucket.consume(500);
+
+        assert!(delay > Duration::from_millis(400));
+        assert!(delay <= Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_throttle_write() {
+        let bucket     = Arc::new(Mutex::new(TokenBucket::new(1000)));
+        let mut writer = Throttle::new(Vec::new(), Some(bucket));
+
+        writer.write_all(b"test content").unwrap();
+
+        assert!(writer.take_delay() > Duration::ZERO);
+        assert_eq!(writer.take_delay(), Duration::ZERO);
+        assert_eq!(writer.inner, b"test content");
+    }
+
+    #[test]
+    fn test_throttle_write_unlimited() {
+        let mut writer = Throttle::new(Vec::new(), None);
+
+        writer.write_all(b"test content").unwrap();
+
+        assert_eq!(writer.take_delay(), Duration::ZERO);
+    }
+}
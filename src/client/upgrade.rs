@@ -0,0 +1,92 @@
+// upgrade: Upgrade status of installed products
+#![forbid(unsafe_code)]
+#![forbid(missing_docs)]
+use semver::Version;
+use serde::Serialize;
+
+/// The upgrade status of an installed product, comparing the newest
+/// installed version with the latest published version.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct UpgradeStatus {
+    /// The name of the product.
+    pub product: String,
+
+    /// The newest installed version of the product.
+    pub installed: String,
+
+    /// The latest published version of the product.
+    pub latest: String,
+
+    /// Whether the latest version is newer than the installed version.
+    pub outdated: bool,
+}
+
+impl UpgradeStatus {
+    /// Create a new [`UpgradeStatus`] for `product`, comparing the
+    /// `installed` and `latest` versions as semver. Versions that aren't
+    /// valid semver are outdated if they differ at all.
+    #[must_use]
+    pub fn new(product: &str, installed: &str, latest: &str) -> Self {
+        let outdated = match (Version::parse(installed), Version::parse(latest)) {
+            (Ok(installed), Ok(latest)) => latest > installed,
+            _                           => installed != latest,
+        };
+
+        Self {
+            product:   product.to_string(),
+            installed: installed.to_string(),
+            latest:    latest.to_string(),
+            outdated:  outdated,
+        }
+    }
+}
+
+// Returns the newest of the given `versions`, comparing them as semver where
+// possible. Versions that aren't valid semver sort before those that are.
+pub(crate) fn newest<'a, I>(versions: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    versions
+        .into_iter()
+        .max_by_key(|version| Version::parse(version).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_new_outdated() {
+        let status = UpgradeStatus::new("terraform", "0.12.9", "0.12.26");
+
+        assert!(status.outdated);
+    }
+
+    #[test]
+    fn test_new_up_to_date() {
+        let status = UpgradeStatus::new("terraform", "0.12.26", "0.12.26");
+
+        assert!(!status.outdated);
+
+        // An installed version newer than the latest isn't outdated.
+        let status = UpgradeStatus::new("terraform", "0.13.0-beta1", "0.12.26");
+
+        assert!(!status.outdated);
+    }
+
+    #[test]
+    fn test_new_invalid_semver() {
+        assert!(UpgradeStatus::new("terraform", "old", "0.12.26").outdated);
+        assert!(!UpgradeStatus::new("terraform", "same", "same").outdated);
+    }
+
+    #[test]
+    fn test_newest() {
+        let versions = ["0.12.9", "0.12.26", "broken", "0.12.10"];
+
+        assert_eq!(newest(versions), Some("0.12.26"));
+        assert_eq!(newest([]), None);
+    }
+}
@@ -4,6 +4,7 @@ use crate::error::Crc32Error;
 use crc32fast::Hasher;
 use std::fs::File;
 use std::io::{
+    self,
     prelude::*,
     BufReader,
 };
@@ -74,6 +75,53 @@ where
     Ok(())
 }
 
+/// A [`Write`] wrapper that calculates the CRC32 of everything written
+/// through it, so content can be checked while it's streamed.
+#[derive(Debug)]
+pub struct Crc32Writer<W> {
+    hasher: Hasher,
+    inner:  W,
+}
+
+impl<W: Write> Crc32Writer<W> {
+    /// Create a new [`Crc32Writer`] wrapping `inner`.
+    pub fn new(inner: W) -> Self {
+        Self {
+            hasher: Hasher::new(),
+            inner:  inner,
+        }
+    }
+
+    /// Check the CRC32 of everything written so far against the `expected`
+    /// CRC32, returning the inner writer.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the CRC32 doesn't match the `expected` value.
+    pub fn check(self, expected: u32) -> Result<W, Crc32Error> {
+        let result = self.hasher.finalize();
+
+        if result != expected {
+            return Err(Crc32Error::UnexpectedCrc32(result, expected));
+        }
+
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for Crc32Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -110,6 +158,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_crc32_writer() {
+        let mut writer = Crc32Writer::new(Vec::new());
+        writer.write_all(b"Test text\n").unwrap();
+
+        let written = writer.check(0x891bc0e8).unwrap();
+
+        assert_eq!(written, b"Test text\n");
+    }
+
+    #[test]
+    fn test_crc32_writer_bad_checksum() {
+        let mut writer = Crc32Writer::new(Vec::new());
+        writer.write_all(b"Test text\n").unwrap();
+
+        let result = writer.check(0x00000000);
+
+        assert!(matches!(result.unwrap_err(), Crc32Error::UnexpectedCrc32(_, _)));
+    }
+
     #[test]
     fn test_check_ok() {
         let test_data = concat!(
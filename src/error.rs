@@ -5,6 +5,28 @@ use thiserror::Error;
 /// Errors encountered in the [`client`] module.
 #[derive(Debug, Error)]
 pub enum ClientError {
+    /// Returned if the configured releases API URL isn't a valid http(s)
+    /// URL.
+    #[error("invalid releases API url '{0}'")]
+    ApiUrl(String),
+
+    /// Returned if a file's SHA256 doesn't match the published shasums.
+    #[error("SHA256 of '{path}' did not match, expected {expected} but got {actual}")]
+    BadChecksum {
+        /// The file that was checked.
+        path: PathBuf,
+
+        /// The expected hex encoded SHA256.
+        expected: String,
+
+        /// The actual hex encoded SHA256 of the file.
+        actual: String,
+    },
+
+    /// Returned if a root certificate file couldn't be read or parsed.
+    #[error("couldn't load certificates from '{0}'")]
+    Certificate(PathBuf),
+
     /// Returned when encountering an error building the [`Client`].
     #[error("couldn't build http client")]
     ClientBuilder,
@@ -13,6 +35,10 @@ pub enum ClientError {
     #[error("couldn't download chunk of content")]
     Chunk,
 
+    /// Returned if there isn't enough disk space to write the download.
+    #[error("not enough disk space to download to '{0}'")]
+    DiskFull(PathBuf),
+
     /// Returned when there's an error getting a [`Url`].
     #[error("couldn't get url '{0}'")]
     Get(url::Url),
@@ -25,23 +51,152 @@ pub enum ClientError {
     #[error("couldn't get text from get request")]
     GetText,
 
+    /// Returned if a configured request header has an invalid name or
+    /// value.
+    #[error("invalid request header '{0}'")]
+    Header(String),
+
+    /// Returned if the server responds with an unsuccessful HTTP status,
+    /// other than not found.
+    #[error("HTTP status {status} while getting url '{url}'")]
+    HttpStatus {
+        /// The URL that was requested.
+        url: url::Url,
+
+        /// The HTTP status code of the response.
+        status: u16,
+    },
+
+    /// Returned if downloaded content doesn't match the MD5 advertised by
+    /// the server in a `Content-MD5` header.
+    #[error("MD5 of content from '{url}' did not match, expected {expected} but got {actual}")]
+    IntegrityMismatch {
+        /// The URL the content was downloaded from. This is kept as a string
+        /// to keep the error small.
+        url: String,
+
+        /// The hex encoded MD5 advertised by the server.
+        expected: String,
+
+        /// The hex encoded MD5 of the downloaded content.
+        actual: String,
+    },
+
     /// Returned if there's an IO error while downloading content.
     #[error(transparent)]
     IoError(#[from] std::io::Error),
 
+    /// Returned if a product version has no changelog.
+    #[error("no changelog is available for {0} {1}")]
+    NoChangelog(String, String),
+
+    /// Returned if a file to verify doesn't exist.
+    #[error("couldn't find '{0}'")]
+    NoSuchFile(PathBuf),
+
+    /// Returned if no published version matches a version constraint.
+    #[error("couldn't find a version matching '{0}'")]
+    NoMatchingVersion(semver::VersionReq),
+
+    /// Returned if a product version has no shasums signatures.
+    #[error("no shasums signatures are available")]
+    NoSignatures,
+
+    /// Returned if a product has no published versions that aren't
+    /// pre-releases.
+    #[error("couldn't find a version of {0} that isn't a pre-release")]
+    NoStableVersion(String),
+
+    /// Returned if the server responds that a [`Url`] doesn't exist.
+    #[error("'{0}' was not found")]
+    NotFound(url::Url),
+
+    /// Returned if a URL isn't the URL of a file published on the HashiCorp
+    /// releases site.
+    #[error("'{url}' isn't a HashiCorp releases URL, {reason}")]
+    NotReleasesUrl {
+        /// The unrecognised URL.
+        url: url::Url,
+
+        /// Why the URL wasn't recognised.
+        reason: &'static str,
+    },
+
+    /// Returned in offline mode if the named artifact isn't in the cache.
+    #[error("'{0}' isn't cached, it can't be fetched in offline mode")]
+    OfflineMiss(String),
+
+    /// Returned if a page of paginated results can't be parsed.
+    #[error("couldn't parse page of results from '{0}'")]
+    Page(url::Url),
+
+    /// Returned if paginating through results doesn't advance, which would
+    /// otherwise loop forever.
+    #[error("pagination of '{0}' stopped advancing")]
+    PaginationStalled(url::Url),
+
+    /// Returned if there's an error configuring a proxy.
+    #[error("couldn't configure proxy")]
+    Proxy,
+
+    /// Returned if the number of bytes downloaded doesn't match the
+    /// advertised content length.
+    #[error("download was truncated, expected {expected} bytes but got {actual}")]
+    ShortDownload {
+        /// The advertised content length.
+        expected: u64,
+
+        /// The number of bytes downloaded.
+        actual: u64,
+    },
+
+    /// Returned if a server ignores the range request for part of a download
+    /// split across multiple connections.
+    #[error("range request to '{0}' was ignored")]
+    RangeIgnored(url::Url),
+
+    /// Returned if the server is still rate limiting requests after waiting
+    /// for as long as it asked.
+    #[error("rate limited while getting url '{0}'")]
+    RateLimited(url::Url),
+
     /// Returned if there's an error parsing the [`ProductVersion`].
     #[error("couldn't parse product version")]
     ProductVersion,
 
+    /// Returned if there's an error parsing the list of products.
+    #[error("couldn't parse products")]
+    Products,
+
+    /// Returned if there's an error checking a file against the
+    /// [`Shasums`].
+    #[error(transparent)]
+    Shasums(#[from] ShasumsError),
+
     /// Returned when there's an error getting a [`Signature`] for the
     /// [`ProductVersion`].
     #[error(transparent)]
     Signature(#[from] SignatureError),
 
+    /// Returned if a download stalls for longer than the read timeout.
+    #[error("timed out waiting for content")]
+    Timeout,
+
     /// Returned if there's [`TmpFile`] error while downloading content.
     #[error(transparent)]
     TmpFile(#[from] TmpFileError),
 
+    /// Returned if a download to memory is larger than the configured
+    /// limit.
+    #[error("'{url}' is larger than the in memory download limit of {max} bytes")]
+    TooLargeForMemory {
+        /// The URL being downloaded.
+        url: url::Url,
+
+        /// The in memory download limit.
+        max: u64,
+    },
+
     /// Returned if there's an error parsing a [`Url`].
     #[error("couldn't parse {0} url")]
     Url(&'static str),
@@ -69,12 +224,121 @@ pub enum InstallError {
     #[error("crc32 error")]
     Crc32(#[from] Crc32Error),
 
+    /// Returned if a product zip doesn't contain the product binary.
+    #[error("couldn't find the {0} binary in the zip")]
+    NoBinary(String),
+
+    /// Returned if the installed products manifest isn't valid.
+    #[error("installed products manifest '{0}' is corrupt")]
+    CorruptManifest(PathBuf),
+
+    /// Returned if the default executable directory couldn't be created.
+    #[error("couldn't create executable dir '{path}': {source}, use --install-dir to install elsewhere")]
+    CreateBinDir {
+        /// The executable directory that couldn't be created.
+        path: PathBuf,
+
+        /// The underlying error.
+        source: std::io::Error,
+    },
+
+    /// Returned if a directory couldn't be created.
+    #[error("couldn't create directory '{path}'")]
+    CreateDir {
+        /// The directory that couldn't be created.
+        path: PathBuf,
+
+        /// The underlying error.
+        source: std::io::Error,
+    },
+
+    /// Returned if the installed products manifest couldn't be read or
+    /// written.
+    #[error("couldn't update installed products manifest '{path}'")]
+    Manifest {
+        /// The manifest path.
+        path: PathBuf,
+
+        /// The underlying error.
+        source: std::io::Error,
+    },
+
+    /// Returned if there's no shared data directory to keep the installed
+    /// products manifest in.
+    #[error("couldn't find shared data directory")]
+    NoDataDir,
+
     // Could not find suitable install-dir. Consider passing --install-dir to
     // manually specify.
     /// Returned if there's no executable directory.
     #[error("no executable dir")]
     NoExecutableDir,
 
+    /// Returned if the user's home directory can't be found.
+    #[error("couldn't find home directory")]
+    NoHomeDir,
+
+    /// Returned if switching to a version of a product that isn't installed.
+    #[error("{0} {1} isn't installed")]
+    VersionNotInstalled(String, String),
+
+    /// Returned if two entries in a zip file would be installed to the same
+    /// destination.
+    #[error("zip file entry '{0}' would overwrite another entry")]
+    DuplicateEntry(String),
+
+    /// Returned if `codesign` rejected the code signature of an installed
+    /// product on macOS.
+    #[error("'{path}' failed code signature verification: {output}")]
+    CodeSignature {
+        /// The installed product binary.
+        path: PathBuf,
+
+        /// The output of `codesign`.
+        output: String,
+    },
+
+    /// Returned if an installed product couldn't be run to verify it.
+    #[error("couldn't run '{path}' to verify it")]
+    Exec {
+        /// The installed product binary.
+        path: PathBuf,
+
+        /// The underlying error.
+        source: std::io::Error,
+    },
+
+    /// Returned if an installed product run to verify it didn't exit in
+    /// time.
+    #[error("'{0}' didn't exit within {1:?} while verifying it")]
+    ExecTimeout(PathBuf, std::time::Duration),
+
+    /// Returned if the output of an installed product didn't contain the
+    /// expected version.
+    #[error("'{path}' didn't report version {expected}, got: {output}")]
+    ExecVerifyFailed {
+        /// The installed product binary.
+        path: PathBuf,
+
+        /// The expected version.
+        expected: String,
+
+        /// The output of the installed product.
+        output: String,
+    },
+
+    /// Returned if a file couldn't be found in the zip file.
+    #[error("couldn't find '{0}' in zip file")]
+    NoSuchFile(String),
+
+    /// Returned if there was nothing to uninstall for a product.
+    #[error("couldn't find {0} to uninstall in '{1}'")]
+    NotInstalled(String, PathBuf),
+
+    /// Returned if the installed product binary couldn't be made executable.
+    #[error("couldn't make '{0}' executable")]
+    NotExecutable(PathBuf),
+
     /// Returned if the installation path is not a directory.
     #[error("install: destination '{0}' is not a directory")]
     NoInstallDir(PathBuf),
@@ -84,11 +348,88 @@ pub enum InstallError {
     #[error("error persisting file")]
     PathPersist(#[from] tempfile::PathPersistError),
 
+    /// Returned if the quarantine attribute of an installed product couldn't
+    /// be cleared on macOS.
+    #[error("couldn't clear the quarantine attribute of '{path}'")]
+    Quarantine {
+        /// The installed product binary.
+        path: PathBuf,
+
+        /// The underlying error.
+        source: std::io::Error,
+    },
+
     /// Returned if there's an error while setting the installed file's
     /// permissions.
     #[error("set permissions error")]
     SetPermissions(#[from] std::io::Error),
 
+    /// Returned if an installed file couldn't be removed.
+    #[error("couldn't remove '{path}'")]
+    Remove {
+        /// The file that couldn't be removed.
+        path: PathBuf,
+
+        /// The underlying error.
+        source: std::io::Error,
+    },
+
+    /// Returned if a shell rc file couldn't be updated to add the install
+    /// directory to the PATH.
+    #[error("couldn't update shell rc file '{path}'")]
+    ShellRc {
+        /// The shell rc file.
+        path: PathBuf,
+
+        /// The underlying error.
+        source: std::io::Error,
+    },
+
+    /// Returned if the `current` symlink of a versioned install couldn't be
+    /// updated.
+    #[error("couldn't update symlink '{path}'")]
+    Symlink {
+        /// The symlink that couldn't be updated.
+        path: PathBuf,
+
+        /// The underlying error.
+        source: std::io::Error,
+    },
+
+    /// Returned if the total uncompressed size of a zip file exceeds the
+    /// limit.
+    #[error("zip file would extract to {size} bytes, over the limit of {max} bytes")]
+    TooLarge {
+        /// The total uncompressed size of the zip file.
+        size: u64,
+
+        /// The maximum allowed size.
+        max: u64,
+    },
+
+    /// Returned if a zip file has more entries than the limit.
+    #[error("zip file has {entries} entries, over the limit of {max}")]
+    TooManyEntries {
+        /// The number of entries in the zip file.
+        entries: usize,
+
+        /// The maximum allowed number of entries.
+        max: usize,
+    },
+
+    /// Returned if a name isn't safe to use as a directory name.
+    #[error("'{0}' is not a safe directory name")]
+    UnsafeDirName(String),
+
+    /// Returned if a name isn't safe to use as a file name.
+    #[error("'{0}' is not a safe file name")]
+    UnsafeFileName(String),
+
+    /// Returned if a zip file entry is absolute or has parent directory
+    /// components.
+    #[error("zip file entry '{0}' has an unsafe path")]
+    UnsafePath(String),
+
     /// Returned if there's an error while getting the zip file basename.
     #[error("couldn't get zip file basename from '{0}'")]
     ZipFileBasename(String),
@@ -98,6 +439,30 @@ pub enum InstallError {
     ZipIndex(#[from] zip::result::ZipError),
 }
 
+/// Errors encountered in the [`pins`] module.
+#[derive(Debug, Error)]
+pub enum PinsError {
+    /// Returned if the pinned versions file couldn't be parsed.
+    #[error("malformed pinned versions file '{path}': {source}")]
+    Malformed {
+        /// The pinned versions file.
+        path: PathBuf,
+
+        /// The underlying parse error.
+        source: toml::de::Error,
+    },
+
+    /// Returned if the pinned versions file exists but couldn't be read.
+    #[error("couldn't read pinned versions file '{path}'")]
+    Read {
+        /// The pinned versions file.
+        path: PathBuf,
+
+        /// The underlying error.
+        source: std::io::Error,
+    },
+}
+
 /// Errors encountered in the [`shasums`] module.
 #[derive(Debug, Error)]
 pub enum ShasumsError {
@@ -105,6 +470,10 @@ pub enum ShasumsError {
     #[error("io error while hashing file")]
     Hashing,
 
+    /// Returned when a line of the shasums content couldn't be parsed.
+    #[error("malformed shasums line: '{0}'")]
+    Malformed(String),
+
     /// Returned when the shasum for a file could not be found.
     #[error("couldn't find shasum for {0}")]
     NoShasumForFile(String),
@@ -117,8 +486,35 @@ pub enum ShasumsError {
 /// Errors encountered in the [`signature`] module.
 #[derive(Debug, Error)]
 pub enum SignatureError {
-    /// Returned if the GPG key path does not exist or is not a file.
-    #[error("gpg key file '{0}' does not exist or is not a file")]
+    /// Returned if the HashiCorp GPG key doesn't have the expected
+    /// fingerprint.
+    #[error("gpg key fingerprint {actual} doesn't match the expected HashiCorp fingerprint {expected}")]
+    FingerprintMismatch {
+        /// The expected fingerprint.
+        expected: String,
+
+        /// The fingerprint of the loaded key.
+        actual: String,
+    },
+
+    /// Returned if a GPG key ID isn't an 8, 16 or 40 character hex string.
+    #[error("invalid gpg key id '{0}', expected 8, 16 or 40 hex characters")]
+    InvalidKeyId(String),
+
+    /// Returned if a signature verifies, but was made by a key other than
+    /// the one required.
+    #[error("signature was made by key {actual}, not the required key {expected}")]
+    KeyMismatch {
+        /// The required key ID.
+        expected: String,
+
+        /// The ID of the key that made the signature.
+        actual: String,
+    },
+
+    /// Returned if the GPG key path does not exist, is not a file, or
+    /// doesn't contain a usable key.
+    #[error("gpg key file '{0}' does not exist or is not a valid key file")]
     GpgKey(PathBuf),
 
     /// Returned when there's an IO error dealing with signature data.
@@ -138,6 +534,15 @@ pub enum SignatureError {
     #[error(transparent)]
     Pgp(#[from] pgp::errors::Error),
 
+    /// Returned if the GPG public key can't be parsed, either armored or
+    /// binary.
+    #[error("couldn't parse gpg public key: {0}")]
+    PublicKey(#[source] pgp::errors::Error),
+
+    /// Returned if the signature can't be parsed, either armored or binary.
+    #[error("couldn't parse signature: {0}")]
+    Signature(#[source] pgp::errors::Error),
+
     /// Returned when the signature couldn't be verified.
     #[error("couldn't verify signature")]
     Verification,
@@ -146,6 +551,10 @@ pub enum SignatureError {
 /// Errors encountered in the [`tmpfile`] module.
 #[derive(Debug, Error)]
 pub enum TmpFileError {
+    /// Returned if there isn't enough disk space to write the file.
+    #[error("not enough disk space to write '{0}'")]
+    DiskFull(PathBuf),
+
     /// Returned if IO errors are encountered.
     #[error(transparent)]
     IoError(#[from] std::io::Error),
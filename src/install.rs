@@ -1,24 +1,38 @@
 // install: Handle installation of product.
 #![forbid(unsafe_code)]
 #![forbid(missing_docs)]
-use super::crc32;
+use super::crc32::Crc32Writer;
 use super::error::InstallError;
+use super::shasums::Sha256Writer;
+use std::collections::HashSet;
 use std::fs;
 use std::io::{
     self,
     Read,
     Seek,
+    Write,
 };
 use std::path::{
+    Component,
     Path,
     PathBuf,
 };
+use std::process::{
+    Command,
+    Stdio,
+};
+use std::thread;
+use std::time::{
+    Duration,
+    Instant,
+};
 use tempfile::{
     NamedTempFile,
     TempPath,
 };
 use zip::{
     read::ZipFile,
+    result::ZipError,
     ZipArchive,
 };
 
@@ -26,121 +40,1836 @@ use zip::{
 use std::fs::Permissions;
 
 #[cfg(target_family = "unix")]
-use std::os::unix::fs::PermissionsExt;
+use std::os::unix::fs::{
+    symlink,
+    PermissionsExt,
+};
+
+#[cfg(target_family = "unix")]
+use std::process;
+
+/// Re-export of `manifest`.
+pub mod manifest;
+
+/// Re-export of `path`.
+pub mod path;
+
+pub use manifest::{
+    Manifest,
+    ManifestEntry,
+};
+
+// Permissions that the product binary must have at least.
+#[cfg(target_family = "unix")]
+const EXECUTABLE_MODE: u32 = 0o755;
+
+// Name of the symlink pointing at the current version in versioned installs.
+#[cfg(target_family = "unix")]
+const CURRENT_LINK: &str = "current";
+
+// Arguments tried, in order, to get an installed product to print its
+// version.
+const VERSION_ARGS: &[&str] = &["version", "--version"];
+
+// How often a product run by verify_exec is checked for having exited.
+const EXEC_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Extended attribute that macOS sets on files downloaded from the internet,
+// causing Gatekeeper to prompt before they're first run.
+#[cfg(target_os = "macos")]
+const QUARANTINE_XATTR: &str = "com.apple.quarantine";
+
+// Default maximum total uncompressed size of a zip to install, 2 GiB.
+const DEFAULT_MAX_SIZE: u64 = 2 * 1024 * 1024 * 1024;
+
+// Default maximum number of entries in a zip to install. HashiCorp zips
+// normally contain one or two files.
+const DEFAULT_MAX_ENTRIES: usize = 16;
+
+/// The classification of an entry in a product zip by [`install_classified`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EntryKind {
+    /// The product binary, named after the product.
+    Binary,
+
+    /// Anything else, such as a license or EULA.
+    Extra,
+}
+
+/// An entry in a product zip, as classified by [`install_classified`] or
+/// [`install_renamed`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClassifiedEntry {
+    /// The filename the entry is installed as.
+    pub filename: PathBuf,
+
+    /// The classification of the entry.
+    pub kind: EntryKind,
+
+    /// Whether the entry was installed, extras may be skipped.
+    pub installed: bool,
+}
+
+// How the permissions of an installed entry are set.
+#[derive(Clone, Copy)]
+enum EntryMode {
+    // Use the mode from the zip.
+    Zip,
+
+    // Ensure that the entry is executable.
+    Executable,
+
+    // Use the mode from the zip, without any executable bits.
+    NotExecutable,
+}
+
+// How the paths of entries in a zip map to paths under the install dir.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Layout {
+    // Install every entry by its basename, directly under the install dir.
+    Flatten,
+
+    // Recreate the directory layout of the zip under the install dir.
+    Preserve,
+}
+
+/// A file installed by [`install`], [`install_preserving`] or
+/// [`install_with_limits`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InstalledEntry {
+    /// The filename the entry is installed as, relative to the install
+    /// directory.
+    pub filename: PathBuf,
+
+    /// The hex encoded SHA256 of the extracted content, computed while
+    /// extracting it.
+    pub sha256: String,
+}
+
+/// The outcome of [`extract_file`].
+#[derive(Debug, Eq, PartialEq)]
+pub struct ExtractedFile {
+    /// The number of bytes written.
+    pub bytes: u64,
+
+    /// The unix mode of the file in the zip, if it has one.
+    pub unix_mode: Option<u32>,
+}
+
+/// The outcome of [`install_versioned`].
+#[derive(Debug)]
+pub struct VersionedInstall {
+    /// The `current` symlink, if symlinks are supported on this platform.
+    pub current: Option<PathBuf>,
+
+    /// The versioned directory that the product was installed to.
+    pub dir: PathBuf,
+
+    /// The full paths of the installed files.
+    pub files: Vec<PathBuf>,
+}
+
+/// The outcome of [`switch`].
+#[derive(Debug)]
+pub struct Switch {
+    /// The `current` symlink, if symlinks are supported on this platform.
+    pub current: Option<PathBuf>,
+
+    /// The version that `current` pointed at before the switch, if any.
+    pub previous: Option<String>,
+
+    /// The version that `current` now points at.
+    pub version: String,
+}
 
 /// `bin_dir` finds a suitable executable directory to install a product to.
 ///
+/// On Windows, which has no standard per-user executable directory, this is
+/// `%LOCALAPPDATA%\hcdl\bin`.
+///
 /// # Errors
 ///
 /// Errors if:
 ///   - Failing to find a suitable executable directory
-///   - Failing to create the executable directory if needed
+///   - Failing to create the executable directory if needed, with
+///     [`InstallError::CreateBinDir`]
 pub fn bin_dir() -> Result<PathBuf, InstallError> {
-    if let Some(dir) = dirs::executable_dir() {
-        // Attempt to create the directory if it doesn't exist
-        if !dir.exists() {
-            fs::create_dir_all(&dir)?;
-        }
+    create_bin_dir(find_bin_dir()?)
+}
+
+/// `find_bin_dir` finds the executable directory that [`bin_dir`] would
+/// return, without creating it.
+///
+/// # Errors
+///
+/// Errors if failing to find a suitable executable directory.
+pub fn find_bin_dir() -> Result<PathBuf, InstallError> {
+    default_bin_dir().ok_or(InstallError::NoExecutableDir)
+}
 
-        Ok(dir)
+// Attempt to create the executable directory at `dir` if it doesn't exist.
+fn create_bin_dir(dir: PathBuf) -> Result<PathBuf, InstallError> {
+    if !dir.exists() {
+        fs::create_dir_all(&dir)
+            .map_err(|source| InstallError::CreateBinDir {
+                path:   dir.clone(),
+                source: source,
+            })?;
     }
-    else {
-        // If we get None, we're likely on Windows.
-        Err(InstallError::NoExecutableDir)
+
+    Ok(dir)
+}
+
+// Find the platform's executable directory.
+#[cfg(not(target_os = "windows"))]
+fn default_bin_dir() -> Option<PathBuf> {
+    dirs::executable_dir()
+}
+
+// Windows doesn't have an executable directory, so we use one under the
+// local application data directory.
+#[cfg(target_os = "windows")]
+fn default_bin_dir() -> Option<PathBuf> {
+    dirs::executable_dir().or_else(|| {
+        dirs::data_local_dir()
+            .map(|dir| dir.join(env!("CARGO_PKG_NAME")).join("bin"))
+    })
+}
+
+/// Limits on the zips that will be installed, guarding against zip bombs.
+#[derive(Clone, Debug)]
+pub struct InstallLimits {
+    /// The maximum number of entries in the zip.
+    pub max_entries: usize,
+
+    /// The maximum total uncompressed size of the entries in the zip, in
+    /// bytes.
+    pub max_size: u64,
+}
+
+impl Default for InstallLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: DEFAULT_MAX_ENTRIES,
+            max_size:    DEFAULT_MAX_SIZE,
+        }
     }
 }
 
 /// Extracts a given `zipfile` to a temporary file under `dir`. Also checks
 /// the CRC32 of the extracted file to make sure extraction was successful.
 /// Returns a [`tempfile::TempPath`] which the caller is responsible for
-/// persisting.
-fn extract(mut zipfile: &mut ZipFile, dir: &Path) -> Result<TempPath, InstallError> {
+/// persisting, along with the hex encoded SHA256 of the extracted content.
+fn extract(
+    zipfile: &mut ZipFile,
+    dir: &Path,
+) -> Result<(TempPath, String), InstallError> {
     // Get a tempfile to extract to under the dest path
-    let mut tmpfile = NamedTempFile::new_in(dir)?;
+    let tmpfile = NamedTempFile::new_in(dir)?;
+
+    // Extract our file, never reading more than the size it claims to have,
+    // calculating the CRC32 and SHA256 as we go. Anything cut short is caught
+    // by the CRC32 check.
+    let size       = zipfile.size();
+    let mut writer = Crc32Writer::new(Sha256Writer::new(tmpfile));
+    io::copy(&mut zipfile.by_ref().take(size), &mut writer)?;
 
-    // Extract our file
-    io::copy(&mut zipfile, &mut tmpfile)?;
+    // Check the file's expected CRC32 against what we wrote, the CRC32
+    // remains the check of the extraction itself.
+    let writer = writer.check(zipfile.crc32())?;
+    let sha256 = writer.hex_digest();
 
     // Closes the file, keeping only the path.
-    let tmpfile = tmpfile.into_temp_path();
+    Ok((writer.into_inner().into_temp_path(), sha256))
+}
+
+/// Ensures that the product binary at `path` is executable, by adding any
+/// missing permissions to give it at least `0o755`. Some zips have no mode
+/// information for their files, leaving them non-executable.
+///
+/// On non-unix platforms this does nothing.
+///
+/// # Errors
+///
+/// Errors with [`InstallError::NotExecutable`] if the permissions of `path`
+/// can't be read or set.
+#[cfg(target_family = "unix")]
+pub fn ensure_executable(path: &Path) -> Result<(), InstallError> {
+    let mode = fs::metadata(path)
+        .map_err(|_err| InstallError::NotExecutable(path.to_path_buf()))?
+        .permissions()
+        .mode();
+
+    if mode & EXECUTABLE_MODE == EXECUTABLE_MODE {
+        return Ok(());
+    }
+
+    fs::set_permissions(path, Permissions::from_mode(mode | EXECUTABLE_MODE))
+        .map_err(|_err| InstallError::NotExecutable(path.to_path_buf()))?;
+
+    Ok(())
+}
+
+/// Ensures that the product binary at `path` is executable, by adding any
+/// missing permissions to give it at least `0o755`. Some zips have no mode
+/// information for their files, leaving them non-executable.
+///
+/// On non-unix platforms this does nothing.
+///
+/// # Errors
+///
+/// Errors with [`InstallError::NotExecutable`] if the permissions of `path`
+/// can't be read or set.
+#[cfg(not(target_family = "unix"))]
+#[allow(clippy::unnecessary_wraps)]
+pub fn ensure_executable(_path: &Path) -> Result<(), InstallError> {
+    Ok(())
+}
+
+/// Runs the installed product `binary` to check that it works and reports
+/// the `expected` version. `binary version` is tried first, followed by
+/// `binary --version`, the check passing if the output of either contains
+/// the `expected` version. Each run is killed if it takes longer than
+/// `timeout`.
+///
+/// # Errors
+///
+/// Errors if:
+///   - The `binary` can't be run
+///   - The `binary` doesn't exit within the `timeout`
+///   - The output doesn't contain the `expected` version
+pub fn verify_exec(
+    binary: &Path,
+    expected: &str,
+    timeout: Duration,
+) -> Result<(), InstallError> {
+    let mut output = String::new();
+
+    for arg in VERSION_ARGS {
+        output = exec_output(binary, arg, timeout)?;
+
+        if output.contains(expected) {
+            return Ok(());
+        }
+    }
+
+    Err(InstallError::ExecVerifyFailed {
+        path:     binary.to_path_buf(),
+        expected: expected.to_string(),
+        output:   output.trim().to_string(),
+    })
+}
+
+// Run `binary` with the single `arg`, returning its combined stdout and
+// stderr. The exit status is ignored, some products exit non-zero after
+// printing their version.
+fn exec_output(
+    binary: &Path,
+    arg: &str,
+    timeout: Duration,
+) -> Result<String, InstallError> {
+    let exec_error = |err| InstallError::Exec {
+        path:   binary.to_path_buf(),
+        source: err,
+    };
+
+    let mut child = Command::new(binary)
+        .arg(arg)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(exec_error)?;
+
+    // Version output is small enough to never fill the pipes, so the child
+    // can't block on them while we wait.
+    let start = Instant::now();
+
+    while child.try_wait().map_err(exec_error)?.is_none() {
+        if start.elapsed() >= timeout {
+            // The child may have exited in the meantime, which is fine.
+            let _ = child.kill();
+            let _ = child.wait();
+
+            return Err(InstallError::ExecTimeout(binary.to_path_buf(), timeout));
+        }
+
+        thread::sleep(EXEC_POLL_INTERVAL);
+    }
+
+    let output = child.wait_with_output().map_err(exec_error)?;
+
+    let mut combined = String::from_utf8_lossy(&output.stdout).into_owned();
+    combined.push_str(&String::from_utf8_lossy(&output.stderr));
+
+    Ok(combined)
+}
+
+/// Clears the `com.apple.quarantine` extended attribute from the installed
+/// `binary`, so that Gatekeeper doesn't prompt before it's first run.
+/// Returns `true` if the attribute was set and has been cleared.
+///
+/// # Errors
+///
+/// Errors if `xattr` can't be run, or fails to remove the attribute.
+#[cfg(target_os = "macos")]
+pub fn clear_quarantine(binary: &Path) -> Result<bool, InstallError> {
+    let quarantine_error = |err| InstallError::Quarantine {
+        path:   binary.to_path_buf(),
+        source: err,
+    };
+
+    // Printing the attribute fails if it isn't set, there's nothing to do.
+    let is_quarantined = Command::new("xattr")
+        .args(["-p", QUARANTINE_XATTR])
+        .arg(binary)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(quarantine_error)?
+        .status
+        .success();
+
+    if !is_quarantined {
+        return Ok(false);
+    }
+
+    let output = Command::new("xattr")
+        .args(["-d", QUARANTINE_XATTR])
+        .arg(binary)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(quarantine_error)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        return Err(quarantine_error(io::Error::other(stderr.trim())));
+    }
+
+    Ok(true)
+}
+
+/// Verifies the code signature of the installed `binary` with
+/// `codesign --verify`.
+///
+/// # Errors
+///
+/// Errors if `codesign` can't be run, or rejects the signature, e.g. because
+/// the `binary` isn't signed.
+#[cfg(target_os = "macos")]
+pub fn verify_codesign(binary: &Path) -> Result<(), InstallError> {
+    let output = Command::new("codesign")
+        .args(["--verify", "--strict"])
+        .arg(binary)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| InstallError::Exec {
+            path:   PathBuf::from("codesign"),
+            source: err,
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        return Err(InstallError::CodeSignature {
+            path:   binary.to_path_buf(),
+            output: stderr.trim().to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Returns the [`Manifest`] of installed products.
+///
+/// # Errors
+///
+/// Errors if:
+///   - The shared data directory can't be found
+///   - The manifest can't be read or is corrupt
+pub fn installed_manifest() -> Result<Manifest, InstallError> {
+    let path = manifest::manifest_path()?;
+
+    Manifest::load(&path)
+}
+
+/// Records the installed product `entry` in the [`Manifest`] of installed
+/// products. The manifest is replaced atomically, and updates from
+/// concurrent installs are serialized.
+///
+/// # Errors
+///
+/// Errors if:
+///   - The shared data directory can't be found
+///   - The existing manifest can't be read or is corrupt
+///   - Failing to write the updated manifest
+pub fn record_install(entry: ManifestEntry) -> Result<(), InstallError> {
+    let path = manifest::manifest_path()?;
+
+    Manifest::record(&path, entry)
+}
+
+/// Extracts the single file `name` from the given `zipfile` into `writer`,
+/// checking the CRC32 of the extracted content. This allows a file to be
+/// extracted without installing it to a directory.
+///
+/// # Errors
+///
+/// Can error if:
+///   - Failing to read the `zipfile`
+///   - The `zipfile` doesn't contain `name`
+///   - Failing to write the extracted content to `writer`
+///   - The CRC32 of the extracted content doesn't match
+///
+/// # Examples
+///
+/// ```no_run
+/// use hcdl::install;
+/// use std::fs::File;
+///
+/// let mut zipfile = File::open("terraform_1.5.7_linux_amd64.zip").unwrap();
+/// let mut binary  = Vec::new();
+///
+/// let extracted = install::extract_file(
+///     &mut zipfile,
+///     "terraform",
+///     &mut binary,
+/// ).unwrap();
+///
+/// assert_eq!(extracted.bytes, binary.len() as u64);
+/// ```
+pub fn extract_file<F, W>(
+    zipfile: &mut F,
+    name: &str,
+    writer: W,
+) -> Result<ExtractedFile, InstallError>
+where
+    F: Read + Seek,
+    W: Write,
+{
+    let mut zip  = ZipArchive::new(zipfile)?;
+    let mut file = zip.by_name(name)
+        .map_err(|err| match err {
+            ZipError::FileNotFound => InstallError::NoSuchFile(name.to_string()),
+            err                    => InstallError::ZipIndex(err),
+        })?;
+
+    let mut writer = Crc32Writer::new(writer);
+    let bytes      = io::copy(&mut file, &mut writer)?;
 
-    // Get the file's expected CRC32 and check against what we wrote.
-    let expected = zipfile.crc32();
-    crc32::check(&tmpfile, expected)?;
+    writer.check(file.crc32())?;
 
-    Ok(tmpfile)
+    let extracted = ExtractedFile {
+        bytes:     bytes,
+        unix_mode: file.unix_mode(),
+    };
+
+    Ok(extracted)
 }
 
-/// Installs files from the given `zipfile` under the directory at `dir`.
+/// Installs files from the given `zipfile` under the directory at `dir`,
+/// within the default [`InstallLimits`]. Returns each installed file with the
+/// SHA256 of its content, computed while it was extracted.
+///
+/// The `zipfile` may be any seekable reader, e.g. a [`std::fs::File`], or a
+/// `Cursor<Bytes>` for a zip held in memory.
 ///
 /// # Errors
 ///
 /// Can error if:
 ///   - Installation directory doesn't exist
 ///   - Failing to get a file index from the `zipfile`
+///   - The `zipfile` exceeds the default [`InstallLimits`]
 ///   - Failing to extract files from the `zipfile`
 ///   - Failing to persist the extracted file
 ///   - Failing to set file permissions on the extracted file
-//
-// type_complexity is allowed here, since attempting to make the suggested
-// type alias results in other complains with no good compiler suggestions.
-#[allow(clippy::type_complexity)]
 pub fn install<F>(
     zipfile: &mut F,
     dir: &Path,
-) -> Result<Vec<PathBuf>, InstallError>
+) -> Result<Vec<InstalledEntry>, InstallError>
+where
+    F: Read + Seek,
+{
+    install_with_limits(zipfile, dir, &InstallLimits::default())
+}
+
+/// Installs files from the given `zipfile` under the directory at `dir`,
+/// within the default [`InstallLimits`], recreating the directory layout of
+/// the `zipfile` instead of installing every file by its basename. Returns
+/// each installed file, relative to `dir`, with the SHA256 of its content.
+///
+/// This is for products that ship a tree of files, rather than a single
+/// binary at the root of the zip. Directories are created as needed, and
+/// entries are never allowed to escape `dir`, even through a symlink that
+/// already exists under it.
+///
+/// # Errors
+///
+/// Can error under the same conditions as [`install`], or if:
+///   - An entry would be installed outside of `dir`
+///   - Failing to create a directory for an entry
+pub fn install_preserving<F>(
+    zipfile: &mut F,
+    dir: &Path,
+) -> Result<Vec<InstalledEntry>, InstallError>
+where
+    F: Read + Seek,
+{
+    let extracted_files = install_entries(
+        zipfile,
+        dir,
+        &InstallLimits::default(),
+        Layout::Preserve,
+        Path::to_path_buf,
+        |_filename| Some(EntryMode::Zip),
+        |_filenames| Ok(()),
+    )?
+    .into_iter()
+    .filter_map(|(filename, sha256)| {
+        sha256.map(|sha256| InstalledEntry {
+            filename: filename,
+            sha256:   sha256,
+        })
+    })
+    .collect();
+
+    Ok(extracted_files)
+}
+
+/// Installs the binary of `product` from the given `zipfile` under the
+/// directory at `dir`, within the default [`InstallLimits`], returning the
+/// classification of every entry in the zip.
+///
+/// Only the entry named after the `product`, e.g. `vault` or `vault.exe`,
+/// is classified as the [`EntryKind::Binary`] and made executable. Any other
+/// entries, such as licenses, are [`EntryKind::Extra`] and are skipped
+/// unless `install_extras` is set, in which case they're installed without
+/// any executable bits.
+///
+/// # Errors
+///
+/// Can error under the same conditions as [`install`], or if the `zipfile`
+/// doesn't contain the `product` binary, in which case nothing is installed.
+pub fn install_classified<F>(
+    zipfile: &mut F,
+    dir: &Path,
+    product: &str,
+    install_extras: bool,
+) -> Result<Vec<ClassifiedEntry>, InstallError>
+where
+    F: Read + Seek,
+{
+    let classify = |filename: &Path| {
+        let is_binary = filename.to_str().is_some_and(|name| {
+            name == product || name == format!("{product}.exe")
+        });
+
+        if is_binary {
+            EntryKind::Binary
+        }
+        else {
+            EntryKind::Extra
+        }
+    };
+
+    let entries = install_entries(
+        zipfile,
+        dir,
+        &InstallLimits::default(),
+        Layout::Flatten,
+        Path::to_path_buf,
+        |filename| {
+            match classify(filename) {
+                EntryKind::Binary                 => Some(EntryMode::Executable),
+                EntryKind::Extra if install_extras => Some(EntryMode::NotExecutable),
+                EntryKind::Extra                  => None,
+            }
+        },
+        |filenames| {
+            filenames
+                .iter()
+                .any(|filename| classify(filename) == EntryKind::Binary)
+                .then_some(())
+                .ok_or_else(|| InstallError::NoBinary(product.to_string()))
+        },
+    )?;
+
+    let classified = entries
+        .into_iter()
+        .map(|(filename, sha256)| {
+            ClassifiedEntry {
+                kind:      classify(&filename),
+                filename:  filename,
+                installed: sha256.is_some(),
+            }
+        })
+        .collect();
+
+    Ok(classified)
+}
+
+/// Installs files from the given `zipfile` under the directory at `dir`,
+/// within the default [`InstallLimits`], installing the binary of `product`
+/// as `name` instead of its name in the zip. Returns the classification of
+/// every entry, with the filenames they were installed as.
+///
+/// Only the entry named after the `product`, e.g. `vault` or `vault.exe`, is
+/// renamed and made executable, any other entries are installed as they are.
+/// If the binary has an `.exe` extension and `name` doesn't, the extension is
+/// kept.
+///
+/// # Errors
+///
+/// Can error under the same conditions as [`install`], or if:
+///   - `name` isn't a single path component
+///   - The `zipfile` doesn't contain the `product` binary
+///   - `name` is the same as another entry in the `zipfile`
+pub fn install_renamed<F>(
+    zipfile: &mut F,
+    dir: &Path,
+    product: &str,
+    name: &str,
+) -> Result<Vec<ClassifiedEntry>, InstallError>
+where
+    F: Read + Seek,
+{
+    if !is_safe_file_name(name) {
+        return Err(InstallError::UnsafeFileName(name.to_string()));
+    }
+
+    let exe_name = format!("{product}.exe");
+
+    let rename = |filename: &Path| {
+        match filename.to_str() {
+            Some(entry) if entry == product => PathBuf::from(name),
+            Some(entry) if entry == exe_name && !name.ends_with(".exe") => {
+                PathBuf::from(format!("{name}.exe"))
+            },
+            Some(entry) if entry == exe_name => PathBuf::from(name),
+            _                                => filename.to_path_buf(),
+        }
+    };
+
+    let is_binary = |filename: &Path| {
+        filename.to_str().is_some_and(|entry| {
+            entry == product || entry == exe_name
+        })
+    };
+
+    let entries = install_entries(
+        zipfile,
+        dir,
+        &InstallLimits::default(),
+        Layout::Flatten,
+        rename,
+        |filename| {
+            if is_binary(filename) {
+                Some(EntryMode::Executable)
+            }
+            else {
+                Some(EntryMode::Zip)
+            }
+        },
+        |filenames| {
+            let Some(binary) = filenames.iter().find(|f| is_binary(f)) else {
+                return Err(InstallError::NoBinary(product.to_string()));
+            };
+
+            let renamed = rename(binary);
+
+            match filenames.iter().find(|f| !is_binary(f) && **f == renamed) {
+                Some(clash) => {
+                    let clash = clash.display().to_string();
+
+                    Err(InstallError::DuplicateEntry(clash))
+                },
+                None => Ok(()),
+            }
+        },
+    )?;
+
+    // Entries are classified by their installed filename, so only the
+    // renamed binary can be the binary.
+    let renamed = rename(Path::new(product));
+    let exe     = rename(Path::new(&exe_name));
+
+    let classified = entries
+        .into_iter()
+        .map(|(filename, sha256)| {
+            let kind = if filename == renamed || filename == exe {
+                EntryKind::Binary
+            }
+            else {
+                EntryKind::Extra
+            };
+
+            ClassifiedEntry {
+                filename:  filename,
+                kind:      kind,
+                installed: sha256.is_some(),
+            }
+        })
+        .collect();
+
+    Ok(classified)
+}
+
+/// Installs files from the given `zipfile` under the directory at `dir`,
+/// refusing to extract anything if the `zipfile` exceeds the given `limits`.
+///
+/// # Errors
+///
+/// Can error under the same conditions as [`install`], with the given
+/// `limits` in place of the defaults.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(dir = %dir.display())),
+)]
+pub fn install_with_limits<F>(
+    zipfile: &mut F,
+    dir: &Path,
+    limits: &InstallLimits,
+) -> Result<Vec<InstalledEntry>, InstallError>
+where
+    F: Read + Seek,
+{
+    let extracted_files = install_entries(
+        zipfile,
+        dir,
+        limits,
+        Layout::Flatten,
+        Path::to_path_buf,
+        |_filename| Some(EntryMode::Zip),
+        |_filenames| Ok(()),
+    )?
+    .into_iter()
+    .filter_map(|(filename, sha256)| {
+        sha256.map(|sha256| InstalledEntry {
+            filename: filename,
+            sha256:   sha256,
+        })
+    })
+    .collect();
+
+    Ok(extracted_files)
+}
+
+// Installs the entries of `zipfile` under `dir`, laid out as per `layout`,
+// as the filename given by `name` for each entry filename, with the
+// permissions given by `mode`, skipping entries it returns `None` for. The
+// filenames of all of the entries are passed to `check` before anything is
+// extracted. Returns the installed filename of every entry and, if it was
+// installed, the SHA256 of its content.
+fn install_entries<F, N, M, C>(
+    zipfile: &mut F,
+    dir: &Path,
+    limits: &InstallLimits,
+    layout: Layout,
+    name: N,
+    mode: M,
+    check: C,
+) -> Result<Vec<(PathBuf, Option<String>)>, InstallError>
 where
     F: Read + Seek,
+    N: Fn(&Path) -> PathBuf,
+    M: Fn(&Path) -> Option<EntryMode>,
+    C: FnOnce(&[PathBuf]) -> Result<(), InstallError>,
 {
     if !dir.is_dir() {
         return Err(InstallError::NoInstallDir(dir.to_path_buf()));
     }
 
-    let mut extracted_files = Vec::new();
-    let mut zip = ZipArchive::new(zipfile)?;
+    let mut entries = Vec::new();
+    let mut zip     = ZipArchive::new(zipfile)?;
+
+    check_limits(&mut zip, limits)?;
+
+    let filenames = entry_filenames(&mut zip, layout)?;
+
+    check(&filenames)?;
+
+    for (i, entry_filename) in filenames.into_iter().enumerate() {
+        let filename = name(&entry_filename);
+
+        let Some(entry_mode) = mode(&entry_filename) else {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(entry = %entry_filename.display(), "skipped");
+
+            entries.push((filename, None));
+            continue;
+        };
 
-    for i in 0..zip.len() {
         let mut file = zip.by_index(i)?;
 
-        // Perform some sanitization on the filename.
-        // We assume here that HashiCorp zips only ever have files at the root
-        // of the zip file, this allows us to not care about their paths.
-        let filename = file.name();
+        // Directories in a preserved layout are only created, there's
+        // nothing to extract.
+        let parent = if layout == Layout::Preserve {
+            let dest = dir.join(&filename);
 
-        // Attempt to get the basename of the filename
-        let basename = Path::new(filename)
-            .file_name()
-            .ok_or_else(|| {
-                InstallError::ZipFileBasename(filename.to_string())
-            })?;
+            if file.is_dir() {
+                create_dir_under(dir, &dest)?;
+
+                entries.push((filename, None));
+                continue;
+            }
 
-        // Finally get a pathbuf of the basename
-        let filename = Path::new(basename).to_path_buf();
+            match dest.parent() {
+                Some(parent) => create_dir_under(dir, parent)?,
+                None         => dir.to_path_buf(),
+            }
+        }
+        else {
+            dir.to_path_buf()
+        };
 
         // Extract the file
-        let tmpfile = extract(&mut file, dir)?;
+        let (tmpfile, sha256) = extract(&mut file, &parent)?;
 
         // Persist the tmpfile to the real dest.
         let dest = dir.join(&filename);
         tmpfile.persist(&dest)?;
 
-        // Set the permissions on the installed file.
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            entry = file.name(),
+            bytes = file.size(),
+            dest = %dest.display(),
+            "extracted"
+        );
+
+        // Set the permissions on the installed file. A mode without any
+        // permission bits is treated the same as having no mode at all.
         #[cfg(target_family = "unix")]
-        if let Some(mode) = file.unix_mode() {
-            fs::set_permissions(&dest, Permissions::from_mode(mode))?;
+        {
+            let zip_mode = file.unix_mode().filter(|mode| mode & 0o777 != 0);
+
+            match (entry_mode, zip_mode) {
+                (EntryMode::Executable, _) => ensure_executable(&dest)?,
+                (EntryMode::Zip, Some(mode)) => {
+                    fs::set_permissions(&dest, Permissions::from_mode(mode))?;
+                },
+                (EntryMode::NotExecutable, Some(mode)) => {
+                    let mode = mode & !0o111;
+
+                    fs::set_permissions(&dest, Permissions::from_mode(mode))?;
+                },
+                (_, None) => {},
+            }
+        }
+
+        #[cfg(not(target_family = "unix"))]
+        if let EntryMode::Executable = entry_mode {
+            ensure_executable(&dest)?;
         }
 
-        extracted_files.push(filename);
+        entries.push((filename, Some(sha256)));
     }
 
-    Ok(extracted_files)
+    Ok(entries)
 }
 
-#[cfg(test)]
-mod tests {
+// Create the directory `path`, which must be under `dir`, returning it.
+// Symlinks already under `dir` could otherwise lead outside of it, so the
+// created directory is checked to really be under `dir`.
+fn create_dir_under(dir: &Path, path: &Path) -> Result<PathBuf, InstallError> {
+    fs::create_dir_all(path)
+        .map_err(|source| InstallError::CreateDir {
+            path:   path.to_path_buf(),
+            source: source,
+        })?;
+
+    let canonical_dir  = fs::canonicalize(dir)?;
+    let canonical_path = fs::canonicalize(path)?;
+
+    if !canonical_path.starts_with(&canonical_dir) {
+        return Err(InstallError::UnsafePath(path.display().to_string()));
+    }
+
+    Ok(path.to_path_buf())
+}
+
+// Get the destination filename of every entry in the zip, before anything
+// is extracted.
+//
+// With the flatten layout, we assume that HashiCorp zips only ever have
+// files at the root of the zip file, so only the basename of each entry is
+// used. With the preserve layout the entry's path is used as it is. Entries
+// that try to escape the install directory are rejected outright, as are
+// entries that would end up installed over each other.
+fn entry_filenames<F>(
+    zip: &mut ZipArchive<F>,
+    layout: Layout,
+) -> Result<Vec<PathBuf>, InstallError>
+where
+    F: Read + Seek,
+{
+    let mut seen      = HashSet::new();
+    let mut filenames = Vec::with_capacity(zip.len());
+
+    for i in 0..zip.len() {
+        let file = zip.by_index_raw(i)?;
+        let name = file.name();
+
+        if is_unsafe_path(name) {
+            return Err(InstallError::UnsafePath(name.to_string()));
+        }
+
+        let filename = match layout {
+            Layout::Flatten => {
+                Path::new(name)
+                    .file_name()
+                    .map(PathBuf::from)
+            },
+            Layout::Preserve => {
+                // Either separator may be used, and empty or current
+                // directory components are dropped.
+                let filename: PathBuf = name
+                    .split(['/', '\\'])
+                    .filter(|component| !matches!(*component, "" | "."))
+                    .collect();
+
+                (!filename.as_os_str().is_empty()).then_some(filename)
+            },
+        }
+        .ok_or_else(|| InstallError::ZipFileBasename(name.to_string()))?;
+
+        if !seen.insert(filename.clone()) {
+            return Err(InstallError::DuplicateEntry(name.to_string()));
+        }
+
+        filenames.push(filename);
+    }
+
+    Ok(filenames)
+}
+
+// Returns true if the zip entry `name` is absolute or has any parent
+// directory components. Both separators are checked, since zips built on
+// Windows may use either.
+fn is_unsafe_path(name: &str) -> bool {
+    name.starts_with(['/', '\\'])
+        || Path::new(name).has_root()
+        || name.split(['/', '\\']).any(|component| component == "..")
+        || name.get(1..2) == Some(":")
+}
+
+// Check the number of entries and their total uncompressed size against
+// `limits`, before anything is extracted.
+fn check_limits<F>(
+    zip: &mut ZipArchive<F>,
+    limits: &InstallLimits,
+) -> Result<(), InstallError>
+where
+    F: Read + Seek,
+{
+    if zip.len() > limits.max_entries {
+        return Err(InstallError::TooManyEntries {
+            entries: zip.len(),
+            max:     limits.max_entries,
+        });
+    }
+
+    let mut size: u64 = 0;
+
+    for i in 0..zip.len() {
+        size = size.saturating_add(zip.by_index_raw(i)?.size());
+    }
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(entries = zip.len(), bytes = size, "zip size");
+
+    if size > limits.max_size {
+        return Err(InstallError::TooLarge {
+            size: size,
+            max:  limits.max_size,
+        });
+    }
+
+    Ok(())
+}
+
+/// Installs files from the given `zipfile` under a versioned directory of
+/// `<base_dir>/<product>/<version>`, then points the
+/// `<base_dir>/<product>/current` symlink at it.
+///
+/// The symlink is replaced atomically. On platforms without symlink support
+/// the symlink is skipped and `current` in the returned [`VersionedInstall`]
+/// is `None`.
+///
+/// # Errors
+///
+/// Can error if:
+///   - `base_dir` doesn't exist
+///   - `product` or `version` aren't safe to use as directory names
+///   - Failing to create the versioned directory
+///   - Failing to install the `zipfile`, as per [`install`]
+///   - Failing to make the product binary executable
+///   - Failing to update the `current` symlink
+pub fn install_versioned<F>(
+    zipfile: &mut F,
+    base_dir: &Path,
+    product: &str,
+    version: &str,
+) -> Result<VersionedInstall, InstallError>
+where
+    F: Read + Seek,
+{
+    if !base_dir.is_dir() {
+        return Err(InstallError::NoInstallDir(base_dir.to_path_buf()));
+    }
+
+    for component in [product, version] {
+        if !is_safe_dir_name(component) {
+            return Err(InstallError::UnsafeDirName(component.to_string()));
+        }
+    }
+
+    let product_dir = base_dir.join(product);
+    let dir         = product_dir.join(version);
+
+    fs::create_dir_all(&dir)
+        .map_err(|source| InstallError::CreateDir {
+            path:   dir.clone(),
+            source: source,
+        })?;
+
+    let files = install(zipfile, &dir)?
+        .into_iter()
+        .map(|entry| dir.join(entry.filename))
+        .collect();
+
+    let binary = dir.join(product);
+    if binary.is_file() {
+        ensure_executable(&binary)?;
+    }
+
+    let current = update_current_link(&product_dir, version)?;
+
+    let installed = VersionedInstall {
+        current: current,
+        dir:     dir,
+        files:   files,
+    };
+
+    Ok(installed)
+}
+
+/// Points the `<base_dir>/<product>/current` symlink at the `version` of
+/// `product` already installed by [`install_versioned`], without installing
+/// anything.
+///
+/// The symlink is replaced atomically. On platforms without symlink support
+/// the symlink is skipped and `current` in the returned [`Switch`] is
+/// `None`.
+///
+/// # Errors
+///
+/// Can error if:
+///   - `base_dir` doesn't exist
+///   - `product` or `version` aren't safe to use as directory names
+///   - The `version` isn't installed under `base_dir`
+///   - Failing to update the `current` symlink
+pub fn switch(
+    product: &str,
+    version: &str,
+    base_dir: &Path,
+) -> Result<Switch, InstallError> {
+    if !base_dir.is_dir() {
+        return Err(InstallError::NoInstallDir(base_dir.to_path_buf()));
+    }
+
+    for component in [product, version] {
+        if !is_safe_dir_name(component) {
+            return Err(InstallError::UnsafeDirName(component.to_string()));
+        }
+    }
+
+    let product_dir = base_dir.join(product);
+
+    if !product_dir.join(version).is_dir() {
+        return Err(InstallError::VersionNotInstalled(
+            product.to_string(),
+            version.to_string(),
+        ));
+    }
+
+    let previous = current_version(product, base_dir);
+    let current  = update_current_link(&product_dir, version)?;
+
+    let switched = Switch {
+        current:  current,
+        previous: previous,
+        version:  version.to_string(),
+    };
+
+    Ok(switched)
+}
+
+/// Returns the version of `product` that the `<base_dir>/<product>/current`
+/// symlink points at, if there is one.
+#[cfg(target_family = "unix")]
+#[must_use]
+pub fn current_version(product: &str, base_dir: &Path) -> Option<String> {
+    let link = base_dir.join(product).join(CURRENT_LINK);
+
+    fs::read_link(link)
+        .ok()
+        .and_then(|target| target.to_str().map(ToString::to_string))
+}
+
+/// Returns the version of `product` that the `<base_dir>/<product>/current`
+/// symlink points at, if there is one.
+///
+/// Symlinks aren't supported on this platform, so this is always `None`.
+#[cfg(not(target_family = "unix"))]
+#[must_use]
+pub fn current_version(_product: &str, _base_dir: &Path) -> Option<String> {
+    None
+}
+
+/// Uninstalls `product` from the directory at `dir`, returning the removed
+/// paths.
+///
+/// Only files named after the `product`, such as `terraform` or
+/// `terraform.exe`, are removed. Symlinks are removed themselves, never their
+/// targets, and only if they point inside `dir`.
+///
+/// # Errors
+///
+/// Can error if:
+///   - `dir` doesn't exist
+///   - `product` isn't safe to use as a file name
+///   - Nothing could be found to uninstall
+///   - Failing to remove a file
+pub fn uninstall(product: &str, dir: &Path) -> Result<Vec<PathBuf>, InstallError> {
+    if !dir.is_dir() {
+        return Err(InstallError::NoInstallDir(dir.to_path_buf()));
+    }
+
+    if !is_safe_dir_name(product) {
+        return Err(InstallError::UnsafeDirName(product.to_string()));
+    }
+
+    let dir = fs::canonicalize(dir)
+        .map_err(|_err| InstallError::NoInstallDir(dir.to_path_buf()))?;
+
+    let mut removed = Vec::new();
+
+    for name in [product.to_string(), format!("{product}.exe")] {
+        let path = dir.join(name);
+
+        // Don't follow symlinks while checking what we've found.
+        let Ok(metadata) = fs::symlink_metadata(&path) else {
+            continue;
+        };
+
+        let file_type = metadata.file_type();
+
+        let owned = if file_type.is_symlink() {
+            // Dangling symlinks fail to canonicalize, and are left alone.
+            fs::canonicalize(&path)
+                .map(|target| target.starts_with(&dir))
+                .unwrap_or(false)
+        }
+        else {
+            file_type.is_file()
+        };
+
+        if !owned {
+            continue;
+        }
+
+        fs::remove_file(&path)
+            .map_err(|source| InstallError::Remove {
+                path:   path.clone(),
+                source: source,
+            })?;
+
+        removed.push(path);
+    }
+
+    if removed.is_empty() {
+        return Err(InstallError::NotInstalled(product.to_string(), dir));
+    }
+
+    Ok(removed)
+}
+
+// Ensure that a name is a single, normal, path component.
+fn is_safe_dir_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    )
+}
+
+/// Returns `true` if `name` is safe to install a file as, i.e. it's a single,
+/// normal, path component without any path separators. Both separators are
+/// checked on every platform.
+#[must_use]
+pub fn is_safe_file_name(name: &str) -> bool {
+    !name.contains(['/', '\\']) && is_safe_dir_name(name)
+}
+
+// Atomically point the `current` symlink under `product_dir` at `version`, by
+// creating a new symlink and renaming it over the old one.
+#[cfg(target_family = "unix")]
+fn update_current_link(
+    product_dir: &Path,
+    version: &str,
+) -> Result<Option<PathBuf>, InstallError> {
+    let current = product_dir.join(CURRENT_LINK);
+    let tmp     = product_dir.join(format!(
+        ".{CURRENT_LINK}.{pid}",
+        pid = process::id(),
+    ));
+
+    // A stale link may have been left behind by an interrupted run, it's
+    // fine if there isn't one.
+    let _ = fs::remove_file(&tmp);
+
+    symlink(version, &tmp)
+        .and_then(|()| fs::rename(&tmp, &current))
+        .map_err(|source| InstallError::Symlink {
+            path:   current.clone(),
+            source: source,
+        })?;
+
+    Ok(Some(current))
+}
+
+// Symlinks aren't supported here, so there's nothing to do.
+#[cfg(not(target_family = "unix"))]
+#[allow(clippy::unnecessary_wraps)]
+fn update_current_link(
+    _product_dir: &Path,
+    _version: &str,
+) -> Result<Option<PathBuf>, InstallError> {
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
     use super::*;
+    use bytes::Bytes;
     use std::fs::File;
+    use std::io::{
+        Cursor,
+        Write,
+    };
     use std::path::Path;
+    use zip::write::SimpleFileOptions;
+    use zip::{
+        CompressionMethod,
+        ZipWriter,
+    };
+
+    // SHA256 of the content of every file in the zips built below.
+    const TEST_CONTENT_SHA256: &str = "6ae8a75555209fd6c44157c0aed8016e763ff435a19cf186f76863140143ff72";
+
+    // Build an in memory zip containing the given files and unix modes.
+    fn zip_with_files(files: &[(&str, u32)]) -> Cursor<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+
+        for (name, mode) in files {
+            let options = SimpleFileOptions::default()
+                .compression_method(CompressionMethod::Stored)
+                .unix_permissions(*mode);
+
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(b"test content").unwrap();
+        }
+
+        zip.finish().unwrap()
+    }
+
+    #[test]
+    fn test_extract_file() {
+        let mut zipfile = zip_with_files(&[
+            ("LICENSE.txt", 0o644),
+            ("terraform", 0o755),
+        ]);
+
+        let mut content = Vec::new();
+        let extracted   = extract_file(
+            &mut zipfile,
+            "terraform",
+            &mut content,
+        ).unwrap();
+
+        // The file type bits may also be set in the mode.
+        let mode = extracted.unix_mode.map(|mode| mode & 0o777);
+
+        assert_eq!(content, b"test content");
+        assert_eq!(extracted.bytes, 12);
+        assert_eq!(mode, Some(0o755));
+    }
+
+    #[test]
+    fn test_extract_file_missing() {
+        let mut zipfile = zip_with_files(&[("terraform", 0o755)]);
+
+        let res = extract_file(&mut zipfile, "vault", io::sink());
+
+        assert!(matches!(res.unwrap_err(), InstallError::NoSuchFile(_)));
+    }
+
+    #[test]
+    fn test_install_classified() {
+        let dir         = tempfile::tempdir().unwrap();
+        let mut zipfile = zip_with_files(&[
+            ("EULA.txt", 0o755),
+            ("vault", 0o644),
+        ]);
+
+        let entries = install_classified(
+            &mut zipfile,
+            dir.path(),
+            "vault",
+            false,
+        ).unwrap();
+
+        let expected = vec![
+            ClassifiedEntry {
+                filename:  PathBuf::from("EULA.txt"),
+                kind:      EntryKind::Extra,
+                installed: false,
+            },
+            ClassifiedEntry {
+                filename:  PathBuf::from("vault"),
+                kind:      EntryKind::Binary,
+                installed: true,
+            },
+        ];
+
+        assert_eq!(entries, expected);
+        assert!(!dir.path().join("EULA.txt").exists());
+
+        #[cfg(target_family = "unix")]
+        {
+            let mode = fs::metadata(dir.path().join("vault"))
+                .unwrap()
+                .permissions()
+                .mode();
+
+            assert_eq!(mode & 0o777, 0o755);
+        }
+    }
+
+    #[test]
+    fn test_install_classified_extras() {
+        let dir         = tempfile::tempdir().unwrap();
+        let mut zipfile = zip_with_files(&[
+            ("EULA.txt", 0o755),
+            ("vault", 0o755),
+        ]);
+
+        let entries = install_classified(
+            &mut zipfile,
+            dir.path(),
+            "vault",
+            true,
+        ).unwrap();
+
+        assert!(entries.iter().all(|entry| entry.installed));
+        assert!(dir.path().join("EULA.txt").exists());
+
+        #[cfg(target_family = "unix")]
+        {
+            let mode = fs::metadata(dir.path().join("EULA.txt"))
+                .unwrap()
+                .permissions()
+                .mode();
+
+            assert_eq!(mode & 0o777, 0o644);
+        }
+    }
+
+    #[test]
+    fn test_install_classified_no_binary() {
+        let dir         = tempfile::tempdir().unwrap();
+        let mut zipfile = zip_with_files(&[("EULA.txt", 0o644)]);
+
+        let res = install_classified(&mut zipfile, dir.path(), "vault", true);
+
+        assert!(matches!(
+            res.unwrap_err(),
+            InstallError::NoBinary(product) if product == "vault",
+        ));
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_install_renamed() {
+        let dir         = tempfile::tempdir().unwrap();
+        let mut zipfile = zip_with_files(&[
+            ("LICENSE.txt", 0o644),
+            ("terraform", 0o644),
+        ]);
+
+        let entries = install_renamed(
+            &mut zipfile,
+            dir.path(),
+            "terraform",
+            "terraform-1.5",
+        ).unwrap();
+
+        let expected = vec![
+            ClassifiedEntry {
+                filename:  PathBuf::from("LICENSE.txt"),
+                kind:      EntryKind::Extra,
+                installed: true,
+            },
+            ClassifiedEntry {
+                filename:  PathBuf::from("terraform-1.5"),
+                kind:      EntryKind::Binary,
+                installed: true,
+            },
+        ];
+
+        assert_eq!(entries, expected);
+        assert!(dir.path().join("LICENSE.txt").exists());
+        assert!(!dir.path().join("terraform").exists());
+
+        #[cfg(target_family = "unix")]
+        {
+            let mode = fs::metadata(dir.path().join("terraform-1.5"))
+                .unwrap()
+                .permissions()
+                .mode();
+
+            assert_eq!(mode & 0o777, 0o755);
+        }
+    }
+
+    #[test]
+    fn test_install_renamed_exe() {
+        let dir         = tempfile::tempdir().unwrap();
+        let mut zipfile = zip_with_files(&[("terraform.exe", 0o644)]);
+
+        let entries = install_renamed(
+            &mut zipfile,
+            dir.path(),
+            "terraform",
+            "terraform-1.5",
+        ).unwrap();
+
+        assert_eq!(entries[0].filename, PathBuf::from("terraform-1.5.exe"));
+        assert_eq!(entries[0].kind, EntryKind::Binary);
+    }
+
+    #[test]
+    fn test_install_renamed_unsafe() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let tests = [
+            "",
+            ".",
+            "..",
+            "../terraform",
+            "bin/terraform",
+            "bin\\terraform",
+            "/usr/bin/terraform",
+        ];
+
+        for name in tests {
+            let mut zipfile = zip_with_files(&[("terraform", 0o755)]);
+
+            let res = install_renamed(&mut zipfile, dir.path(), "terraform", name);
+
+            assert!(matches!(
+                res.unwrap_err(),
+                InstallError::UnsafeFileName(n) if n == name,
+            ));
+        }
+
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_install_renamed_clash() {
+        let dir         = tempfile::tempdir().unwrap();
+        let mut zipfile = zip_with_files(&[
+            ("LICENSE.txt", 0o644),
+            ("terraform", 0o755),
+        ]);
+
+        let res = install_renamed(
+            &mut zipfile,
+            dir.path(),
+            "terraform",
+            "LICENSE.txt",
+        );
+
+        assert!(matches!(
+            res.unwrap_err(),
+            InstallError::DuplicateEntry(name) if name == "LICENSE.txt",
+        ));
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_install_parent_dir() {
+        let dir         = tempfile::tempdir().unwrap();
+        let mut zipfile = zip_with_files(&[
+            ("terraform", 0o755),
+            ("../evil", 0o755),
+        ]);
+
+        let res = install(&mut zipfile, dir.path());
+
+        assert!(matches!(
+            res.unwrap_err(),
+            InstallError::UnsafePath(name) if name == "../evil",
+        ));
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_install_absolute_path() {
+        let dir         = tempfile::tempdir().unwrap();
+        let mut zipfile = zip_with_files(&[("/etc/evil", 0o755)]);
+
+        let res = install(&mut zipfile, dir.path());
+
+        assert!(matches!(
+            res.unwrap_err(),
+            InstallError::UnsafePath(name) if name == "/etc/evil",
+        ));
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_install_duplicate_entry() {
+        let dir         = tempfile::tempdir().unwrap();
+        let mut zipfile = zip_with_files(&[
+            ("terraform", 0o755),
+            ("bin/terraform", 0o755),
+        ]);
+
+        let res = install(&mut zipfile, dir.path());
+
+        assert!(matches!(
+            res.unwrap_err(),
+            InstallError::DuplicateEntry(name) if name == "bin/terraform",
+        ));
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_install_preserving() {
+        let dir         = tempfile::tempdir().unwrap();
+        let mut zipfile = zip_with_files(&[
+            ("terraform", 0o755),
+            ("bin/terraform", 0o755),
+            ("share/doc/./README", 0o644),
+        ]);
+
+        let installed = install_preserving(&mut zipfile, dir.path()).unwrap();
+
+        let filenames: Vec<PathBuf> = installed
+            .into_iter()
+            .map(|entry| entry.filename)
+            .collect();
+
+        let expected = vec![
+            PathBuf::from("terraform"),
+            Path::new("bin").join("terraform"),
+            Path::new("share").join("doc").join("README"),
+        ];
+
+        assert_eq!(filenames, expected);
+
+        for filename in &expected {
+            assert!(dir.path().join(filename).is_file());
+        }
+    }
+
+    #[test]
+    fn test_install_preserving_parent_dir() {
+        let dir         = tempfile::tempdir().unwrap();
+        let mut zipfile = zip_with_files(&[
+            ("bin/terraform", 0o755),
+            ("bin/../../evil", 0o755),
+        ]);
+
+        let res = install_preserving(&mut zipfile, dir.path());
+
+        assert!(matches!(
+            res.unwrap_err(),
+            InstallError::UnsafePath(name) if name == "bin/../../evil",
+        ));
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_install_preserving_symlink() {
+        let dir         = tempfile::tempdir().unwrap();
+        let outside     = tempfile::tempdir().unwrap();
+        let mut zipfile = zip_with_files(&[("bin/evil", 0o755)]);
+
+        symlink(outside.path(), dir.path().join("bin")).unwrap();
+
+        let res = install_preserving(&mut zipfile, dir.path());
+
+        assert!(matches!(res.unwrap_err(), InstallError::UnsafePath(_)));
+        assert_eq!(fs::read_dir(outside.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_is_unsafe_path() {
+        assert!(is_unsafe_path("../evil"));
+        assert!(is_unsafe_path("bin/../../evil"));
+        assert!(is_unsafe_path("..\\evil"));
+        assert!(is_unsafe_path("/etc/evil"));
+        assert!(is_unsafe_path("\\evil"));
+        assert!(is_unsafe_path("C:\\evil"));
+        assert!(!is_unsafe_path("terraform"));
+        assert!(!is_unsafe_path("bin/terraform"));
+        assert!(!is_unsafe_path("terraform..bak"));
+    }
+
+    // echo prints the arguments it's given, so it reports the "version" it's
+    // asked for.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_verify_exec() {
+        let res = verify_exec(
+            Path::new("/bin/echo"),
+            "version",
+            Duration::from_secs(5),
+        );
+
+        assert!(res.is_ok());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_verify_exec_mismatch() {
+        let res = verify_exec(
+            Path::new("/bin/echo"),
+            "1.6.0-hcdl",
+            Duration::from_secs(5),
+        );
+
+        assert!(matches!(
+            res.unwrap_err(),
+            InstallError::ExecVerifyFailed { expected, .. } if expected == "1.6.0-hcdl",
+        ));
+    }
+
+    #[test]
+    fn test_verify_exec_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let res = verify_exec(
+            &dir.path().join("terraform"),
+            "1.6.0",
+            Duration::from_secs(5),
+        );
+
+        assert!(matches!(res.unwrap_err(), InstallError::Exec { .. }));
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_clear_quarantine() {
+        let file = NamedTempFile::new().unwrap();
+
+        assert!(!clear_quarantine(file.path()).unwrap());
+
+        let status = Command::new("xattr")
+            .args(["-w", QUARANTINE_XATTR, "0081;00000000;hcdl;"])
+            .arg(file.path())
+            .status()
+            .unwrap();
+
+        assert!(status.success());
+        assert!(clear_quarantine(file.path()).unwrap());
+        assert!(!clear_quarantine(file.path()).unwrap());
+    }
+
+    // A plain file has no code signature to verify.
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_verify_codesign_unsigned() {
+        let file = NamedTempFile::new().unwrap();
+
+        assert!(matches!(
+            verify_codesign(file.path()).unwrap_err(),
+            InstallError::CodeSignature { .. },
+        ));
+    }
+
+    #[test]
+    fn test_install_too_many_entries() {
+        let dir     = tempfile::tempdir().unwrap();
+        let limits  = InstallLimits {
+            max_entries: 2,
+            ..InstallLimits::default()
+        };
+        let mut zipfile = zip_with_files(&[
+            ("LICENSE.txt", 0o644),
+            ("README.md", 0o644),
+            ("terraform", 0o755),
+        ]);
+
+        let res = install_with_limits(&mut zipfile, dir.path(), &limits);
+
+        assert!(matches!(
+            res.unwrap_err(),
+            InstallError::TooManyEntries { entries: 3, max: 2 },
+        ));
+
+        // Nothing should have been extracted.
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_install_too_large() {
+        let dir     = tempfile::tempdir().unwrap();
+        let limits  = InstallLimits {
+            max_size: 20,
+            ..InstallLimits::default()
+        };
+        let mut zipfile = zip_with_files(&[
+            ("LICENSE.txt", 0o644),
+            ("terraform", 0o755),
+        ]);
+
+        let res = install_with_limits(&mut zipfile, dir.path(), &limits);
+
+        assert!(matches!(
+            res.unwrap_err(),
+            InstallError::TooLarge { size: 24, max: 20 },
+        ));
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_install_from_bytes() {
+        let dir     = tempfile::tempdir().unwrap();
+        let zipfile = zip_with_files(&[("terraform", 0o755)]).into_inner();
+
+        let mut zipfile = Cursor::new(Bytes::from(zipfile));
+
+        let installed = install(&mut zipfile, dir.path()).unwrap();
+
+        let expected = vec![
+            InstalledEntry {
+                filename: PathBuf::from("terraform"),
+                sha256:   TEST_CONTENT_SHA256.to_string(),
+            },
+        ];
+
+        assert_eq!(installed, expected);
+        assert_eq!(
+            fs::read(dir.path().join("terraform")).unwrap(),
+            b"test content",
+        );
+    }
 
     #[test]
     fn test_install_dir_not_dir() {
@@ -158,4 +1887,179 @@ mod tests {
 
         assert!(res.is_err());
     }
+
+    #[test]
+    fn test_uninstall() {
+        let dir = tempfile::tempdir().unwrap();
+
+        fs::write(dir.path().join("terraform"), b"test content").unwrap();
+        fs::write(dir.path().join("vault"), b"test content").unwrap();
+
+        let removed = uninstall("terraform", dir.path()).unwrap();
+        let dir     = fs::canonicalize(dir.path()).unwrap();
+
+        assert_eq!(removed, vec![dir.join("terraform")]);
+        assert!(!dir.join("terraform").exists());
+        assert!(dir.join("vault").exists());
+    }
+
+    #[test]
+    fn test_uninstall_not_installed() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let res = uninstall("terraform", dir.path());
+
+        assert!(matches!(res.unwrap_err(), InstallError::NotInstalled(_, _)));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_create_bin_dir_unwritable_parent() {
+        let parent = tempfile::tempdir().unwrap();
+        let dir    = parent.path().join("bin");
+
+        fs::set_permissions(parent.path(), Permissions::from_mode(0o555))
+            .unwrap();
+
+        // Permissions don't stop root, so there's nothing to test.
+        if tempfile::tempfile_in(parent.path()).is_ok() {
+            return;
+        }
+
+        let res = create_bin_dir(dir.clone());
+
+        fs::set_permissions(parent.path(), Permissions::from_mode(0o755))
+            .unwrap();
+
+        let err = res.unwrap_err();
+
+        assert!(err.to_string().contains("--install-dir"));
+        assert!(matches!(
+            err,
+            InstallError::CreateBinDir { path, .. } if path == dir,
+        ));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_uninstall_symlink_outside_dir() {
+        let dir     = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let target  = outside.path().join("terraform");
+
+        fs::write(&target, b"test content").unwrap();
+        symlink(&target, dir.path().join("terraform")).unwrap();
+
+        let res = uninstall("terraform", dir.path());
+
+        assert!(matches!(res.unwrap_err(), InstallError::NotInstalled(_, _)));
+        assert!(dir.path().join("terraform").exists());
+        assert!(target.exists());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_install_versioned() {
+        let base_dir = tempfile::tempdir().unwrap();
+
+        for version in ["1.5.7", "1.6.0"] {
+            let mut zipfile = zip_with_files(&[("terraform", 0o755)]);
+
+            let installed = install_versioned(
+                &mut zipfile,
+                base_dir.path(),
+                "terraform",
+                version,
+            ).unwrap();
+
+            let dir = base_dir.path().join("terraform").join(version);
+
+            assert_eq!(installed.dir, dir);
+            assert_eq!(installed.files, vec![dir.join("terraform")]);
+            assert!(dir.join("terraform").is_file());
+        }
+
+        let current = base_dir.path().join("terraform").join(CURRENT_LINK);
+
+        assert_eq!(fs::read_link(&current).unwrap(), Path::new("1.6.0"));
+        assert!(current.join("terraform").is_file());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_switch() {
+        let base_dir = tempfile::tempdir().unwrap();
+
+        for version in ["1.5.7", "1.6.0"] {
+            let mut zipfile = zip_with_files(&[("terraform", 0o755)]);
+
+            install_versioned(
+                &mut zipfile,
+                base_dir.path(),
+                "terraform",
+                version,
+            ).unwrap();
+        }
+
+        let switched = switch("terraform", "1.5.7", base_dir.path()).unwrap();
+        let current  = base_dir.path().join("terraform").join(CURRENT_LINK);
+
+        assert_eq!(switched.current, Some(current.clone()));
+        assert_eq!(switched.previous.as_deref(), Some("1.6.0"));
+        assert_eq!(switched.version, "1.5.7");
+        assert_eq!(fs::read_link(&current).unwrap(), Path::new("1.5.7"));
+        assert_eq!(
+            current_version("terraform", base_dir.path()).as_deref(),
+            Some("1.5.7"),
+        );
+    }
+
+    #[test]
+    fn test_switch_not_installed() {
+        let base_dir = tempfile::tempdir().unwrap();
+
+        let res = switch("terraform", "1.5.7", base_dir.path());
+
+        assert!(matches!(
+            res.unwrap_err(),
+            InstallError::VersionNotInstalled(product, version)
+                if product == "terraform" && version == "1.5.7",
+        ));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_install_versioned_zero_mode() {
+        let base_dir    = tempfile::tempdir().unwrap();
+        let mut zipfile = zip_with_files(&[("terraform", 0)]);
+
+        let installed = install_versioned(
+            &mut zipfile,
+            base_dir.path(),
+            "terraform",
+            "1.6.0",
+        ).unwrap();
+
+        let mode = fs::metadata(installed.dir.join("terraform"))
+            .unwrap()
+            .permissions()
+            .mode();
+
+        assert_eq!(mode & EXECUTABLE_MODE, EXECUTABLE_MODE);
+    }
+
+    #[test]
+    fn test_install_versioned_unsafe_version() {
+        let base_dir    = tempfile::tempdir().unwrap();
+        let mut zipfile = zip_with_files(&[("terraform", 0o755)]);
+
+        let res = install_versioned(
+            &mut zipfile,
+            base_dir.path(),
+            "terraform",
+            "../1.6.0",
+        );
+
+        assert!(matches!(res.unwrap_err(), InstallError::UnsafeDirName(_)));
+    }
 }
@@ -0,0 +1,369 @@
+// manifest: Record installed products
+#![forbid(unsafe_code)]
+#![forbid(missing_docs)]
+use crate::error::InstallError;
+use chrono::{
+    DateTime,
+    Utc,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use sha2::{
+    Digest,
+    Sha256,
+};
+use std::fs::{
+    self,
+    File,
+};
+use std::io::{
+    self,
+    BufWriter,
+    Write,
+};
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::sync::Mutex;
+use tempfile::NamedTempFile;
+
+const MANIFEST_FILENAME: &str = "installed.json";
+
+// Serializes updates of the manifest between concurrent installs in this
+// process, other processes are protected from partial writes by the
+// write-rename.
+static MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// A file installed as part of a [`ManifestEntry`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct InstalledFile {
+    /// The full path of the installed file.
+    pub path: PathBuf,
+
+    /// The SHA256 of the file when it was installed.
+    pub sha256: String,
+}
+
+/// A single installed product version.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ManifestEntry {
+    /// The arch of the installed build.
+    pub arch: String,
+
+    /// The directory the product was installed to.
+    pub dir: PathBuf,
+
+    /// The files that were installed.
+    pub files: Vec<InstalledFile>,
+
+    /// When the product was installed.
+    pub installed_at: DateTime<Utc>,
+
+    /// The OS of the installed build.
+    pub os: String,
+
+    /// The name of the product.
+    pub product: String,
+
+    /// The version of the product.
+    pub version: String,
+}
+
+impl ManifestEntry {
+    /// Create a new [`ManifestEntry`] for the `files` installed to `dir`,
+    /// recording the SHA256 of each file.
+    ///
+    /// # Errors
+    ///
+    /// Errors if any of the `files` can't be read.
+    pub fn new(
+        product: &str,
+        version: &str,
+        os: &str,
+        arch: &str,
+        dir: &Path,
+        files: &[PathBuf],
+    ) -> Result<Self, InstallError> {
+        let files = files
+            .iter()
+            .map(|path| {
+                let file = InstalledFile {
+                    path:   path.clone(),
+                    sha256: sha256(path)?,
+                };
+
+                Ok(file)
+            })
+            .collect::<Result<Vec<_>, InstallError>>()?;
+
+        let entry = Self {
+            arch:         arch.to_string(),
+            dir:          dir.to_path_buf(),
+            files:        files,
+            installed_at: Utc::now(),
+            os:           os.to_string(),
+            product:      product.to_string(),
+            version:      version.to_string(),
+        };
+
+        Ok(entry)
+    }
+
+    /// Returns `true` if all of the installed files still exist and match
+    /// the SHA256 recorded when they were installed.
+    #[must_use]
+    pub fn is_intact(&self) -> bool {
+        self.files.iter().all(|file| {
+            sha256(&file.path)
+                .map(|sha256| sha256 == file.sha256)
+                .unwrap_or(false)
+        })
+    }
+}
+
+/// The manifest of installed products.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Manifest {
+    /// The installed product versions.
+    pub installed: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    /// Load the [`Manifest`] at `path`. A missing manifest is treated as
+    /// empty.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the manifest can't be read or is corrupt.
+    pub fn load(path: &Path) -> Result<Self, InstallError> {
+        let content = match fs::read(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            },
+            Err(source) => {
+                return Err(InstallError::Manifest {
+                    path:   path.to_path_buf(),
+                    source: source,
+                });
+            },
+        };
+
+        let manifest = serde_json::from_slice(&content)
+            .map_err(|_err| InstallError::CorruptManifest(path.to_path_buf()))?;
+
+        Ok(manifest)
+    }
+
+    /// Find the entry for the given `product`, `version`, `os` and `arch`
+    /// installed to `dir`, if there is one.
+    #[must_use]
+    pub fn find(
+        &self,
+        product: &str,
+        version: &str,
+        os: &str,
+        arch: &str,
+        dir: &Path,
+    ) -> Option<&ManifestEntry> {
+        self.installed.iter().find(|entry| {
+            entry.product == product
+                && entry.version == version
+                && entry.os == os
+                && entry.arch == arch
+                && entry.dir == dir
+        })
+    }
+
+    /// Add `entry` to the [`Manifest`] at `path`, replacing any existing
+    /// entry for the same product installed in the same directory.
+    ///
+    /// # Errors
+    ///
+    /// Errors if:
+    ///   - The existing manifest can't be read or is corrupt
+    ///   - Failing to write the updated manifest
+    pub fn record(path: &Path, entry: ManifestEntry) -> Result<(), InstallError> {
+        // A poisoned lock only means another install panicked, the manifest
+        // on disk is still whole thanks to the write-rename.
+        let _lock = MANIFEST_LOCK
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        let mut manifest = Self::load(path)?;
+
+        manifest.installed.retain(|installed| {
+            installed.product != entry.product || installed.dir != entry.dir
+        });
+        manifest.installed.push(entry);
+
+        manifest.save(path)
+    }
+
+    // Atomically write the manifest to `path`, by writing a temporary file
+    // in the same directory and renaming it into place.
+    fn save(&self, path: &Path) -> Result<(), InstallError> {
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+        let manifest_err = |source| InstallError::Manifest {
+            path:   path.to_path_buf(),
+            source: source,
+        };
+
+        fs::create_dir_all(dir).map_err(manifest_err)?;
+
+        let tmpfile    = NamedTempFile::new_in(dir).map_err(manifest_err)?;
+        let mut writer = BufWriter::new(tmpfile.as_file());
+
+        serde_json::to_writer_pretty(&mut writer, self)
+            .map_err(io::Error::from)
+            .map_err(manifest_err)?;
+
+        writer.flush().map_err(manifest_err)?;
+        drop(writer);
+
+        tmpfile.persist(path)
+            .map_err(|err| manifest_err(err.error))?;
+
+        Ok(())
+    }
+}
+
+/// Returns the path of the installed products manifest.
+///
+/// # Errors
+///
+/// Errors if the shared data directory can't be found.
+pub fn manifest_path() -> Result<PathBuf, InstallError> {
+    let path = dirs::data_dir()
+        .ok_or(InstallError::NoDataDir)?
+        .join(env!("CARGO_PKG_NAME"))
+        .join(MANIFEST_FILENAME);
+
+    Ok(path)
+}
+
+// Calculate the SHA256 of the file at `path`.
+pub(crate) fn sha256(path: &Path) -> Result<String, InstallError> {
+    let hash_err = |source| InstallError::Manifest {
+        path:   path.to_path_buf(),
+        source: source,
+    };
+
+    let mut file   = File::open(path).map_err(hash_err)?;
+    let mut hasher = Sha256::new();
+
+    io::copy(&mut file, &mut hasher).map_err(hash_err)?;
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn entry(dir: &Path, product: &str) -> ManifestEntry {
+        let binary = dir.join(product);
+        fs::write(&binary, b"test content").unwrap();
+
+        ManifestEntry::new(product, "1.6.0", "linux", "amd64", dir, &[binary])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_load_missing() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let manifest = Manifest::load(&dir.path().join(MANIFEST_FILENAME))
+            .unwrap();
+
+        assert_eq!(manifest, Manifest::default());
+    }
+
+    #[test]
+    fn test_load_corrupt() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join(MANIFEST_FILENAME);
+
+        fs::write(&path, b"not json").unwrap();
+
+        let res = Manifest::load(&path);
+
+        assert!(matches!(res.unwrap_err(), InstallError::CorruptManifest(_)));
+    }
+
+    #[test]
+    fn test_record() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hcdl").join(MANIFEST_FILENAME);
+
+        let terraform = entry(dir.path(), "terraform");
+        let vault     = entry(dir.path(), "vault");
+
+        Manifest::record(&path, terraform.clone()).unwrap();
+        Manifest::record(&path, vault.clone()).unwrap();
+
+        // Reinstalling replaces the existing entry.
+        Manifest::record(&path, terraform.clone()).unwrap();
+
+        let manifest = Manifest::load(&path).unwrap();
+
+        assert_eq!(manifest.installed, vec![vault, terraform]);
+    }
+
+    #[test]
+    fn test_record_corrupt() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join(MANIFEST_FILENAME);
+
+        fs::write(&path, b"not json").unwrap();
+
+        let res = Manifest::record(&path, entry(dir.path(), "terraform"));
+
+        assert!(matches!(res.unwrap_err(), InstallError::CorruptManifest(_)));
+        assert_eq!(fs::read(&path).unwrap(), b"not json");
+    }
+
+    #[test]
+    fn test_find() {
+        let dir      = tempfile::tempdir().unwrap();
+        let entry    = entry(dir.path(), "terraform");
+        let manifest = Manifest {
+            installed: vec![entry.clone()],
+        };
+
+        let found = manifest.find("terraform", "1.6.0", "linux", "amd64", dir.path());
+
+        assert_eq!(found, Some(&entry));
+        assert!(manifest.find("terraform", "1.5.7", "linux", "amd64", dir.path()).is_none());
+    }
+
+    #[test]
+    fn test_is_intact() {
+        let dir   = tempfile::tempdir().unwrap();
+        let entry = entry(dir.path(), "terraform");
+
+        assert!(entry.is_intact());
+
+        fs::write(dir.path().join("terraform"), b"changed content").unwrap();
+
+        assert!(!entry.is_intact());
+    }
+
+    #[test]
+    fn test_sha256() {
+        let path = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test-data/test.txt",
+        ));
+
+        assert_eq!(
+            sha256(path).unwrap(),
+            "66d5b2cc06203137f8a0e9714638dc1085c57a3f1fa26c8823ae5cf89ab26488",
+        );
+    }
+}
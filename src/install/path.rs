@@ -0,0 +1,268 @@
+// path: Check and update the PATH the install dir needs to be in
+#![forbid(unsafe_code)]
+#![forbid(missing_docs)]
+use crate::error::InstallError;
+use std::env;
+use std::ffi::OsStr;
+use std::fs::{
+    self,
+    OpenOptions,
+};
+use std::io::{
+    self,
+    Write,
+};
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+// Marks the lines we add to shell rc files.
+const RC_COMMENT: &str = "# Added by hcdl";
+
+/// The shells whose rc files [`ensure_in_path`] can add the install dir to
+/// the PATH in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Shell {
+    /// The Bourne Again SHell.
+    Bash,
+
+    /// The friendly interactive shell.
+    Fish,
+
+    /// The Z shell.
+    Zsh,
+}
+
+impl Shell {
+    /// Detect the user's shell from the `SHELL` environment variable,
+    /// returning `None` if it isn't set or isn't a known shell.
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let shell = env::var_os("SHELL")?;
+
+        Self::from_path(Path::new(&shell))
+    }
+
+    // Detect the shell from the path to its binary.
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.file_name().and_then(OsStr::to_str)? {
+            "bash" => Some(Self::Bash),
+            "fish" => Some(Self::Fish),
+            "zsh"  => Some(Self::Zsh),
+            _      => None,
+        }
+    }
+
+    /// Returns the line that adds `dir` to the PATH for this shell.
+    #[must_use]
+    pub fn export_line(self, dir: &Path) -> String {
+        let dir = self.quote(&dir.display().to_string());
+
+        match self {
+            Self::Bash | Self::Zsh => format!("export PATH={dir}:\"$PATH\""),
+            Self::Fish             => format!("set -gx PATH {dir} $PATH"),
+        }
+    }
+
+    // Single quote `s` for this shell, so that nothing in it is expanded
+    // when the rc file is sourced.
+    fn quote(self, s: &str) -> String {
+        let escaped = match self {
+            Self::Bash | Self::Zsh => s.replace('\'', "'\\''"),
+            Self::Fish             => s.replace('\\', "\\\\").replace('\'', "\\'"),
+        };
+
+        format!("'{escaped}'")
+    }
+
+    /// Returns the path of the rc file for this shell, under the user's
+    /// `home` directory.
+    #[must_use]
+    pub fn rc_file(self, home: &Path) -> PathBuf {
+        match self {
+            Self::Bash => home.join(".bashrc"),
+            Self::Fish => home.join(".config").join("fish").join("config.fish"),
+            Self::Zsh  => home.join(".zshrc"),
+        }
+    }
+}
+
+/// Returns `true` if `dir` is one of the directories in the `PATH`
+/// environment variable.
+#[must_use]
+pub fn is_in_path(dir: &Path) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|path| path_contains(&path, dir))
+}
+
+// Returns `true` if `dir` is one of the directories in `path`, comparing
+// canonical paths where possible so that symlinks and trailing slashes don't
+// matter.
+fn path_contains(path: &OsStr, dir: &Path) -> bool {
+    let canonical = |dir: &Path| {
+        fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())
+    };
+
+    let dir = canonical(dir);
+
+    env::split_paths(path)
+        .filter(|entry| !entry.as_os_str().is_empty())
+        .any(|entry| canonical(&entry) == dir)
+}
+
+/// Adds `dir` to the PATH in the rc file of `shell`, under the user's home
+/// directory. This is idempotent, the rc file is left alone if it already
+/// adds `dir` to the PATH.
+///
+/// Returns the path of the rc file, and whether it was changed.
+///
+/// # Errors
+///
+/// Errors if:
+///   - The user's home directory can't be found
+///   - Failing to read or update the rc file
+pub fn ensure_in_path(
+    dir: &Path,
+    shell: Shell,
+) -> Result<(PathBuf, bool), InstallError> {
+    let home    = dirs::home_dir().ok_or(InstallError::NoHomeDir)?;
+    let rc_file = shell.rc_file(&home);
+    let line    = shell.export_line(dir);
+
+    let changed = append_line(&rc_file, &line)
+        .map_err(|source| InstallError::ShellRc {
+            path:   rc_file.clone(),
+            source: source,
+        })?;
+
+    Ok((rc_file, changed))
+}
+
+// Append `line` to the file at `path`, unless the file already has that
+// line. Returns `true` if the file was changed.
+fn append_line(path: &Path, line: &str) -> io::Result<bool> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+
+    if content.lines().any(|existing| existing.trim() == line) {
+        return Ok(false);
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut file = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)?;
+
+    // Don't run our line into the end of the last one.
+    if !content.is_empty() && !content.ends_with('\n') {
+        writeln!(file)?;
+    }
+
+    writeln!(file, "\n{RC_COMMENT}\n{line}")?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::ffi::OsString;
+
+    #[test]
+    fn test_shell_from_path() {
+        let tests = vec![
+            ("/bin/bash", Some(Shell::Bash)),
+            ("/usr/local/bin/fish", Some(Shell::Fish)),
+            ("/bin/zsh", Some(Shell::Zsh)),
+            ("/bin/sh", None),
+            ("", None),
+        ];
+
+        for (path, expected) in tests {
+            assert_eq!(Shell::from_path(Path::new(path)), expected, "{path}");
+        }
+    }
+
+    #[test]
+    fn test_export_line() {
+        let dir = Path::new("/home/test/.local/bin");
+
+        assert_eq!(
+            Shell::Bash.export_line(dir),
+            "export PATH='/home/test/.local/bin':\"$PATH\"",
+        );
+
+        assert_eq!(
+            Shell::Fish.export_line(dir),
+            "set -gx PATH '/home/test/.local/bin' $PATH",
+        );
+
+        // Nothing in the directory is expanded by the shell.
+        let dir = Path::new("/home/it's $(test)/bin");
+
+        assert_eq!(
+            Shell::Zsh.export_line(dir),
+            "export PATH='/home/it'\\''s $(test)/bin':\"$PATH\"",
+        );
+
+        let dir = Path::new("/home/it's \\ $(test)/bin");
+
+        assert_eq!(
+            Shell::Fish.export_line(dir),
+            "set -gx PATH '/home/it\\'s \\\\ $(test)/bin' $PATH",
+        );
+    }
+
+    #[test]
+    fn test_path_contains() {
+        let dir   = tempfile::tempdir().unwrap();
+        let other = tempfile::tempdir().unwrap();
+
+        let path = env::join_paths([other.path(), dir.path()]).unwrap();
+
+        assert!(path_contains(&path, dir.path()));
+        assert!(path_contains(&path, &dir.path().join(".")));
+        assert!(!path_contains(&path, &dir.path().join("missing")));
+        assert!(!path_contains(&OsString::new(), dir.path()));
+    }
+
+    #[test]
+    fn test_append_line() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config").join("rc");
+        let line = "export PATH=\"/test:$PATH\"";
+
+        assert!(append_line(&path, line).unwrap());
+
+        // Appending the same line again changes nothing.
+        assert!(!append_line(&path, line).unwrap());
+
+        let expected = format!("\n{RC_COMMENT}\n{line}\n");
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_append_line_no_trailing_newline() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rc");
+        let line = "export PATH=\"/test:$PATH\"";
+
+        fs::write(&path, "alias ll='ls -l'").unwrap();
+
+        assert!(append_line(&path, line).unwrap());
+
+        let expected = format!("alias ll='ls -l'\n\n{RC_COMMENT}\n{line}\n");
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+    }
+}
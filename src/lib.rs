@@ -16,6 +16,9 @@ pub mod error;
 /// Handle extracting and installing downloaded product.
 pub mod install;
 
+/// Handle product versions pinned in a versions file.
+pub mod pins;
+
 /// Handle drawing progress bars during download and install.
 pub mod progressbar;
 
@@ -1,123 +1,941 @@
 //! hcdl: Easily update Hashicorp tools
 #![forbid(unsafe_code)]
 #![forbid(missing_docs)]
-use anyhow::Result;
+use anyhow::{
+    bail,
+    Context,
+    Result,
+};
+use clap::ArgMatches;
+use clap::parser::ValueSource;
+use futures::future::join_all;
 use hcdl::{
     client,
     install,
+    progressbar,
     shasums,
 };
+use hcdl::client::Client;
+use hcdl::client::constraint;
+use hcdl::error::{
+    ClientError,
+    InstallError,
+    ShasumsError,
+    SignatureError,
+};
+use hcdl::install::{
+    EntryKind,
+    ManifestEntry,
+};
+use hcdl::install::path::Shell as PathShell;
+use hcdl::pins::{
+    self,
+    Pins,
+};
+use hcdl::shasums::Shasums;
+use hcdl::signature;
 use hcdl::tmpfile::TmpFile;
-use std::path::PathBuf;
+use semver::Version;
+use std::fs::{
+    self,
+    File,
+};
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::env::consts::EXE_SUFFIX;
 use std::process::exit;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::timeout;
+use url::Url;
 
 mod cli;
 mod messages;
 mod products;
 
-use messages::Messages;
+use messages::{
+    Messages,
+    OutputFormat,
+};
+use products::ProductMatch;
 
 #[cfg(feature = "shell_completion")]
 use clap_complete::Shell;
 
-const LATEST: &str = "latest";
+// How long an installed product has to report its version with
+// --verify-exec.
+const VERIFY_EXEC_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Exit codes, allowing automation to tell failures apart.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ExitCode {
+    // Generic and argument errors.
+    Failure   = 1,
+
+    // The requested version, or something it needs, couldn't be found.
+    NotFound  = 2,
+
+    // The shasums signature couldn't be verified.
+    Signature = 3,
+
+    // The download didn't match its published SHA256.
+    Checksum  = 4,
+
+    // The product couldn't be installed or uninstalled.
+    Install   = 5,
+
+    // A network request failed.
+    Network   = 6,
+
+    // The --deadline expired, matching timeout(1).
+    Deadline  = 124,
+}
+
+impl ExitCode {
+    // Classify `error` by the first error in its chain that we recognise.
+    fn from_error(error: &anyhow::Error) -> Self {
+        error
+            .chain()
+            .find_map(|err| {
+                if let Some(err) = err.downcast_ref::<ClientError>() {
+                    Self::from_client_error(err)
+                }
+                else if err.is::<SignatureError>() {
+                    Some(Self::Signature)
+                }
+                else if err.is::<InstallError>() {
+                    Some(Self::Install)
+                }
+                else {
+                    None
+                }
+            })
+            .unwrap_or(Self::Failure)
+    }
+
+    fn from_client_error(error: &ClientError) -> Option<Self> {
+        let code = match error {
+            ClientError::BadChecksum { .. }
+            | ClientError::IntegrityMismatch { .. } => Self::Checksum,
+            ClientError::NoSignatures
+            | ClientError::Signature(_)     => Self::Signature,
+            ClientError::NoChangelog(_, _)
+            | ClientError::NoMatchingVersion(_)
+            | ClientError::NotFound(_)      => Self::NotFound,
+            ClientError::Chunk
+            | ClientError::Get(_)
+            | ClientError::GetBytes
+            | ClientError::GetText
+            | ClientError::HttpStatus { .. }
+            | ClientError::RateLimited(_)
+            | ClientError::ShortDownload { .. }
+            | ClientError::Timeout          => Self::Network,
+            _                               => return None,
+        };
+
+        Some(code)
+    }
+
+    fn exit(self) -> ! {
+        exit(self as i32)
+    }
+}
 
-#[allow(clippy::too_many_lines)]
 #[tokio::main]
 async fn main() -> Result<()> {
     let matches  = cli::parse_args();
 
+    #[cfg(feature = "tracing")]
+    init_tracing(matches.get_count("VERBOSE"));
+
+    let is_quiet = matches.get_flag("QUIET");
+    let no_color = cli::no_color();
+    let format   = OutputFormat::from(
+        matches.get_one::<String>("FORMAT").unwrap().as_str(),
+    );
+    let messages = Messages::new(is_quiet, format);
+
     #[cfg(feature = "shell_completion")]
     // Generate completions if requested
     {
         if matches.contains_id("COMPLETIONS") {
             // This was validated during CLI parse.
             let shell = matches.get_one::<Shell>("COMPLETIONS").unwrap();
-            cli::gen_completions(*shell);
+
+            if matches.get_flag("INSTALL_COMPLETIONS") {
+                let path = install_completions(*shell)?;
+
+                messages.completions_installed(&shell.to_string(), &path);
+            }
+            else {
+                cli::gen_completions(*shell, &mut std::io::stdout());
+            }
 
             exit(0);
         }
     }
 
-    let is_quiet = matches.get_flag("QUIET");
-    let no_color = cli::no_color();
-    let messages = Messages::new(is_quiet);
+    // Generate a man page if requested
+    #[cfg(feature = "man_page")]
+    if matches.get_flag("MAN") {
+        cli::gen_man(&mut std::io::stdout())?;
+
+        exit(0);
+    }
+
+    // Print what we've installed.
+    if matches.get_flag("INSTALLED") {
+        let manifest = install::installed_manifest()?;
+
+        messages.installed(&manifest);
+
+        exit(0);
+    }
+
+    // Print the fingerprint of the GPG key we'd verify against, so it can be
+    // checked against the one HashiCorp publishes.
+    if matches.get_flag("GPG_FINGERPRINT") {
+        let path        = matches.get_one::<PathBuf>("GPG_KEY");
+        let fingerprint = signature::public_key_fingerprint(
+            path.map(PathBuf::as_path),
+        )?;
+
+        messages.gpg_fingerprint(&fingerprint);
+
+        exit(0);
+    }
+
+    // Print where we'd install to, for use in scripts.
+    if matches.get_flag("PRINT_INSTALL_DIR") {
+        let dir = install_dir(&matches)?;
+
+        messages.print_install_dir(&dir);
+
+        exit(0);
+    }
+
+    // Install from a local zip, the client isn't needed at all.
+    if let Some(path) = matches.get_one::<PathBuf>("FROM_FILE") {
+        if let Err(e) = install_from_file(&messages, &matches, path) {
+            messages.product_failed(&path.display().to_string(), &e);
+
+            ExitCode::from_error(&e).exit();
+        }
+
+        exit(0);
+    }
+
+    // --quiet only silences messages, the progress bar is drawn unless it's
+    // disabled or we're in JSON mode, to keep the output machine readable.
+    // Without a terminal it's only drawn if forced. Products installed
+    // concurrently draw their bars together, rather than over each other.
+    let no_progress = matches.get_flag("NO_PROGRESS")
+        || format == OutputFormat::Json;
+
+    let event_messages    = messages.clone();
+    let mut client_config = client::ClientConfig::new()
+        .enterprise(matches.get_flag("ENTERPRISE"))
+        .force_progress(matches.get_flag("FORCE_PROGRESS"))
+        .head_precheck(true)
+        .include_prerelease(matches.get_flag("INCLUDE_PRERELEASE"))
+        .mirror_headers(matches.get_flag("MIRROR_HEADERS"))
+        .multi_progress(Arc::new(progressbar::multi_progress()))
+        .no_color(no_color)
+        .offline(matches.get_flag("OFFLINE"))
+        .quiet(no_progress)
+        .on_event(move |event| event_messages.client_event(event));
+
+    if let Some(limit) = matches.get_one::<u64>("MAX_BYTES_PER_SEC") {
+        client_config = client_config.bandwidth_limit(Some(*limit));
+    }
+
+    if let Some(mirrors) = matches.get_many::<Url>("MIRROR") {
+        client_config = client_config.mirrors(mirrors.cloned().collect());
+    }
+
+    if let Some(dir) = matches.get_one::<PathBuf>("CACHE_DIR") {
+        client_config = client_config.cache_dir(dir.clone());
+    }
+
+    if let Some(path) = matches.get_one::<PathBuf>("GPG_KEY") {
+        client_config = client_config.gpg_key_path(path.clone());
+    }
+
+    if let Some(key_id) = matches.get_one::<String>("REQUIRE_KEY") {
+        client_config = client_config.require_key_id(key_id.clone());
+    }
+
+    // Print the configuration the client would be built with, for
+    // diagnosing which settings are in effect.
+    if matches.get_flag("PRINT_CONFIG") {
+        let config = client_config.effective()?;
+
+        messages.config(&config);
+
+        exit(0);
+    }
+
+    let client = Client::new(client_config)?;
+
+    // The whole of the remaining flow, including every network request, has
+    // to complete within the deadline if one was given. Dropping the flow on
+    // expiry cleans up any partial downloads.
+    let deadline = matches
+        .get_one::<u64>("DEADLINE")
+        .map(|secs| Duration::from_secs(*secs));
+
+    let Some(deadline) = deadline else {
+        return run(&client, &messages, &matches).await;
+    };
+
+    let Ok(res) = timeout(deadline, run(&client, &messages, &matches)).await else {
+        messages.deadline_exceeded(deadline);
+
+        ExitCode::Deadline.exit();
+    };
+
+    res
+}
+
+// Log diagnostics to stderr, keeping stdout for the usual output. Any -v
+// flags take priority over RUST_LOG, without either nothing is logged.
+#[cfg(feature = "tracing")]
+fn init_tracing(verbosity: u8) {
+    use tracing_subscriber::EnvFilter;
+
+    let filter = match verbosity {
+        0 => match EnvFilter::try_from_default_env() {
+            Ok(filter) => filter,
+            Err(_err)  => return,
+        },
+        1 => EnvFilter::new("hcdl=debug"),
+        _ => EnvFilter::new("hcdl=trace,reqwest=debug"),
+    };
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
+// Run the product listing, or check, download, verify and install the
+// requested products.
+async fn run(
+    client: &Client,
+    messages: &Messages,
+    matches: &ArgMatches,
+) -> Result<()> {
+    // Refresh the cached list of known products, which is otherwise only
+    // read from disk.
+    if matches.get_flag("REFRESH_PRODUCTS") {
+        let discovered = client.all_products().await?;
+        let path       = products::store(&discovered)?;
+
+        messages.products_refreshed(discovered.len(), &path);
+    }
+
+    // Replace the stored GPG key, which may have gone stale after HashiCorp
+    // rotated their signing subkeys.
+    if matches.get_flag("REFRESH_GPG_KEY") {
+        let (fingerprint, backup) = client.refresh_gpg_key().await?;
+
+        messages.gpg_key_refreshed(&fingerprint, backup.as_deref());
+    }
+
+    // Check that everything needed to install works, without installing
+    // anything.
+    if matches.get_flag("SELFTEST") {
+        let dir    = install_dir(matches)?;
+        let report = client.healthcheck(&dir).await?;
+
+        messages.healthcheck(&report);
+
+        if !report.passed() {
+            ExitCode::Failure.exit();
+        }
+
+        return Ok(());
+    }
 
     // We don't need to do very much if we're listing products
     if matches.get_flag("LIST_PRODUCTS") {
-        messages.list_products(products::PRODUCTS_LIST);
+        let products = discover_products(client, messages).await;
+
+        messages.list_products(&products);
 
         exit(0);
     };
 
+    // Check every installed product for upgrades, nothing is downloaded.
+    if matches.get_flag("CHECK_ALL") {
+        let manifest = install::installed_manifest()?;
+
+        // A product that can't be checked doesn't stop the others being
+        // reported, the exit code is that of the first failure.
+        let mut statuses = Vec::new();
+        let mut failed   = None;
+
+        for (product, status) in client.check_all(&manifest).await {
+            match status {
+                Ok(status) => statuses.push(status),
+                Err(e)     => {
+                    messages.check_failed(&product, &e);
+
+                    failed.get_or_insert_with(|| {
+                        ExitCode::from_client_error(&e).unwrap_or(ExitCode::Failure)
+                    });
+                },
+            }
+        }
+
+        if !statuses.is_empty() || failed.is_none() {
+            messages.check_all(&statuses);
+        }
+
+        if let Some(code) = failed {
+            code.exit();
+        }
+
+        return Ok(());
+    }
+
+    // Products are only optional when there's something else to do, such as
+    // refreshing the products cache.
+    let Some(names) = matches.get_many::<String>("PRODUCT") else {
+        return Ok(());
+    };
+
+    // Resolve the requested products, allowing unambiguous abbreviations of
+    // known products.
+    let known        = products::known();
+    let mut products = Vec::new();
+    let mut unknown  = Vec::new();
+
+    for name in names {
+        match products::find(name, &known) {
+            ProductMatch::Exact(product) => products.push(product.to_string()),
+            ProductMatch::Assumed(product) => {
+                messages.assumed_product(name, product);
+
+                products.push(product.to_string());
+            },
+            ProductMatch::Ambiguous(candidates) => {
+                messages.ambiguous_product(name, &candidates);
+
+                ExitCode::Failure.exit();
+            },
+            ProductMatch::NoMatch => {
+                products.push(name.to_string());
+                unknown.push(name.as_str());
+            },
+        }
+    }
+
+    // Products missing from the known list may have been published since
+    // this release or the last refresh, so they're checked against the
+    // releases API.
+    if !unknown.is_empty() {
+        let known = discover_products(client, messages).await;
+
+        let unknown: Vec<&str> = unknown
+            .into_iter()
+            .filter(|name| !known.iter().any(|product| product == name))
+            .collect();
+
+        if !unknown.is_empty() {
+            messages.unknown_products(&unknown);
+
+            ExitCode::Failure.exit();
+        }
+    }
+
+    // Every product would be installed over the same name.
+    if matches.contains_id("RENAME") && products.len() > 1 {
+        bail!("--rename can only be used when installing a single product");
+    }
+
+    // Every product would write its checksum over the same file.
+    if matches.contains_id("CHECKSUM_FILE") && products.len() > 1 {
+        bail!("--checksum-file can only be used when installing a single product");
+    }
+
+    // Versions may be pinned per product in the current directory, these
+    // are used unless a --build is given.
+    let pins = Pins::load(Path::new(pins::PINS_FILE))?;
+
+    // Products are installed concurrently, a failure of one product doesn't
+    // stop the others from completing.
+    let results = join_all(products.iter().map(|product| {
+        install_product(client, messages, matches, pins.as_ref(), product)
+    })).await;
+
+    // The exit code is that of the first product to fail.
+    let mut failed = None;
+
+    for (product, result) in products.iter().zip(results) {
+        if let Err(e) = result {
+            messages.product_failed(product, &e);
+
+            failed.get_or_insert_with(|| ExitCode::from_error(&e));
+        }
+    }
+
+    if let Some(code) = failed {
+        code.exit();
+    }
+
+    Ok(())
+}
+
+// Get the static list of products merged with those discovered from the
+// releases API, falling back to the static list alone if discovery fails.
+async fn discover_products(client: &Client, messages: &Messages) -> Vec<String> {
+    match client.all_products().await {
+        Ok(discovered) => products::merge(&discovered),
+        Err(e)         => {
+            messages.product_discovery_failed(&e);
+
+            products::known()
+        },
+    }
+}
+
+// Tell the user how to add `dir` to their PATH, or with --ensure-path add it
+// to the rc file of their shell. Failing to update the rc file only warns,
+// the install itself succeeded.
+fn ensure_path(messages: &Messages, matches: &ArgMatches, dir: &Path) {
+    let shell = PathShell::from_env();
+
+    if let Some(shell) = shell.filter(|_| matches.get_flag("ENSURE_PATH")) {
+        match install::path::ensure_in_path(dir, shell) {
+            Ok((rc_file, changed)) => messages.path_updated(dir, &rc_file, changed),
+            Err(e)                 => messages.path_update_failed(&e),
+        }
+
+        return;
+    }
+
+    let export = shell.map(|shell| shell.export_line(dir));
+
+    messages.ensure_in_path(dir, export.as_deref());
+}
+
+// Try to get an install_dir.
+fn install_dir(matches: &ArgMatches) -> Result<PathBuf> {
+    let dir = if let Some(dir) = matches.get_one::<PathBuf>("INSTALL_DIR") {
+        // If a --install-dir was given, use that. We validated this in the
+        // CLI so we know this is good.
+        dir.clone()
+    }
+    else {
+        install::bin_dir()?
+    };
+
+    Ok(dir)
+}
+
+// As install_dir, but without creating the default install dir.
+fn find_install_dir(matches: &ArgMatches) -> Result<PathBuf> {
+    let dir = if let Some(dir) = matches.get_one::<PathBuf>("INSTALL_DIR") {
+        dir.clone()
+    }
+    else {
+        install::find_bin_dir()?
+    };
+
+    Ok(dir)
+}
+
+// Write the completions for `shell` to where the shell loads them from,
+// creating the directory if needed, and return the path written to.
+#[cfg(feature = "shell_completion")]
+fn install_completions(shell: Shell) -> Result<PathBuf> {
+    let Some(path) = cli::completions_path(shell) else {
+        bail!("installing completions isn't supported for {shell}");
+    };
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("couldn't create '{}'", dir.display()))?;
+    }
+
+    let mut file = File::create(&path)
+        .with_context(|| format!("couldn't create '{}'", path.display()))?;
+
+    cli::gen_completions(shell, &mut file);
+
+    Ok(path)
+}
+
+// Install the local zip at `path`, after checking it against the local
+// shasums file given with --verify-with, if any.
+fn install_from_file(
+    messages: &Messages,
+    matches: &ArgMatches,
+    path: &Path,
+) -> Result<()> {
+    let filename = path
+        .file_name()
+        .map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+
+    let mut zipfile = File::open(path)
+        .with_context(|| format!("couldn't open '{}'", path.display()))?;
+
+    if let Some(shasums_path) = matches.get_one::<PathBuf>("VERIFY_WITH") {
+        let content = fs::read_to_string(shasums_path)
+            .with_context(|| {
+                format!("couldn't read '{}'", shasums_path.display())
+            })?;
+
+        match Shasums::new(content).check_file(path)? {
+            shasums::Checksum::Ok => messages.checksum_ok(&filename),
+            shasums::Checksum::Mismatch { expected, actual } => {
+                messages.checksum_bad(&filename, &expected, &actual);
+
+                bail!(ClientError::BadChecksum {
+                    path: path.to_path_buf(),
+                    expected,
+                    actual,
+                });
+            },
+            shasums::Checksum::Missing => {
+                bail!(
+                    "couldn't find {filename} in '{}'",
+                    shasums_path.display(),
+                );
+            },
+        };
+    }
+
+    let bin_dir = install_dir(matches)?;
+
+    messages.unzipping(&filename, &bin_dir);
+
+    // An invalid zip is reported as an InstallError::ZipIndex.
+    let extracted_files = install::install(&mut zipfile, &bin_dir)?;
+
+    // Any products given name the binaries in the zip.
+    if let Some(products) = matches.get_many::<String>("PRODUCT") {
+        for product in products {
+            let binary = bin_dir.join(product);
+            if binary.is_file() {
+                install::ensure_executable(&binary)?;
+            }
+        }
+    }
+
+    for entry in &extracted_files {
+        messages.extracted_file(&entry.filename, &bin_dir);
+    }
+
+    messages.installation_successful();
+
+    Ok(())
+}
+
+// Check, download, verify, and install a single product according to the
+// options in `matches`.
+#[allow(clippy::too_many_lines)]
+async fn install_product(
+    client: &Client,
+    messages: &Messages,
+    matches: &ArgMatches,
+    pins: Option<&Pins>,
+    product: &str,
+) -> Result<()> {
+    // Uninstall only, nothing to download.
+    if matches.get_flag("UNINSTALL") {
+        let dir     = install_dir(matches)?;
+        let removed = install::uninstall(product, &dir)?;
+
+        messages.uninstalled(product, &removed);
+
+        return Ok(());
+    }
+
+    if matches.get_flag("ENTERPRISE")
+        && !products::ENTERPRISE_PRODUCTS_LIST.contains(&product)
+    {
+        bail!("{product} has no enterprise variant");
+    }
+
+    // The version that the current symlink pointed at before a --use, for
+    // reporting once the requested version has been installed.
+    let mut previous = None;
+
+    // Switching to an installed version doesn't need to download anything.
+    // Otherwise the version is installed as usual, which switches to it.
+    if let Some(version) = matches.get_one::<String>("USE") {
+        let base_dir = install_dir(matches)?;
+        let dir      = base_dir.join(product).join(version);
+
+        let installed = install::installed_manifest()?
+            .installed
+            .iter()
+            .any(|entry| {
+                entry.product == product
+                    && entry.version == *version
+                    && entry.dir == dir
+                    && entry.is_intact()
+            });
+
+        if installed {
+            let switched = install::switch(product, version, &base_dir)?;
+
+            messages.switched(
+                product,
+                switched.previous.as_deref(),
+                &switched.version,
+                switched.current.as_deref(),
+            );
+
+            return Ok(());
+        }
+
+        if matches.get_flag("OFFLINE") {
+            bail!(InstallError::VersionNotInstalled(
+                product.to_string(),
+                version.to_string(),
+            ));
+        }
+
+        previous = install::current_version(product, &base_dir);
+    }
+
     // Pull options from matches
     // Unwraps here should be fine as these are checked and have default
     // values.
-    let build_version = matches.get_one::<String>("BUILD").unwrap();
-    let product       = matches.get_one::<String>("PRODUCT").unwrap();
+    let build_version = matches
+        .get_one::<String>("USE")
+        .or_else(|| matches.get_one::<String>("BUILD"))
+        .unwrap();
 
-    let client_config = client::ClientConfig::new()
-        .no_color(no_color)
-        .quiet(is_quiet);
+    // A pinned version replaces the default of latest, but not an explicit
+    // --build or --use. Checks are always against the latest version.
+    let pinned = pins
+        .filter(|_| {
+            !matches.get_flag("CHECK")
+                && !matches.contains_id("USE")
+                && matches.value_source("BUILD") == Some(ValueSource::DefaultValue)
+        })
+        .and_then(|pins| pins.version(product));
+
+    let build_version = match pinned {
+        Some(version) => {
+            messages.pinned_version(product, version, pins::PINS_FILE);
 
-    let client = client::Client::new(client_config)?;
+            version
+        },
+        None => build_version.as_str(),
+    };
 
-    let builds = if build_version.to_lowercase() == LATEST {
+    // Latest is resolved here rather than by get_version, as we report it and
+    // may only be checking it.
+    let builds = if build_version.eq_ignore_ascii_case(client::LATEST) {
         let latest = client.check_version(product).await?;
 
-        messages.latest_version(&latest.to_string());
+        messages.latest_version(&latest);
 
         // Check only, no download.
         if matches.get_flag("CHECK") {
-            exit(0);
+            return Ok(());
         }
 
-        client.get_version(product, &latest.version).await?
+        client.get_version(product, &latest.version)
+            .await
+            .map_err(|err| version_error(err, product, &latest.version))?
+    }
+    else if Version::parse(build_version).is_ok() {
+        // An exact version was requested.
+        client.get_version(product, build_version)
+            .await
+            .map_err(|err| version_error(err, product, build_version))?
+    }
+    else if let Ok(constraint) = constraint::parse(build_version) {
+        let resolved = client.resolve_version(product, &constraint).await?;
+
+        messages.resolved_version(build_version, &resolved);
+
+        resolved
     }
     else {
-        client.get_version(product, build_version).await?
+        client.get_version(product, build_version)
+            .await
+            .map_err(|err| version_error(err, product, build_version))?
     };
 
+    // Changelog only, no download.
+    if matches.get_flag("CHANGELOG") {
+        let changelog = client.get_changelog(product, &builds.version).await?;
+
+        messages.changelog(product, &builds.version, &changelog);
+
+        return Ok(());
+    }
+
+    // List builds only, no download.
+    if matches.get_flag("LIST_BUILDS") {
+        messages.list_builds(product, &builds.version, &builds.builds);
+
+        return Ok(());
+    }
+
+    // Verify an already downloaded file only, no download.
+    if let Some(path) = matches.get_one::<PathBuf>("VERIFY") {
+        #[cfg(not(feature = "embed_gpg_key"))]
+        if !matches.contains_id("GPG_KEY") {
+            client.ensure_gpg_key().await?;
+        }
+
+        client.verify_file(&builds, path).await?;
+
+        messages.verified_file(path);
+
+        return Ok(());
+    }
+
     let arch = matches.get_one::<String>("ARCH").unwrap();
     let os   = matches.get_one::<String>("OS").unwrap();
 
-    let Some(build) = builds.build(arch, os) else {
+    let Some((build, fallback)) = builds.build_with_fallback(arch, os) else {
         messages.find_build_failed(os, arch);
 
-        exit(1);
+        bail!("no build for {os}-{arch}");
     };
 
+    if fallback {
+        messages.build_fallback(os, arch, &build.arch);
+    }
+
+    // Skip the install if this exact build is already installed and intact,
+    // unless we're forced to reinstall.
+    if !matches.get_flag("FORCE")
+        && !matches.get_flag("DOWNLOAD_ONLY")
+        && !matches.get_flag("DRY_RUN")
+        && !matches.get_flag("PRINT_CHECKSUM")
+    {
+        let dir = if matches.get_flag("VERSIONED") {
+            install_dir(matches)?.join(product).join(&builds.version)
+        }
+        else {
+            install_dir(matches)?
+        };
+
+        // A renamed binary is only installed if it has the name asked for.
+        let binary  = dir.join(binary_name(matches, product));
+        let renamed = matches.contains_id("RENAME");
+
+        match install::installed_manifest() {
+            Ok(manifest) => {
+                let installed = manifest
+                    .find(product, &builds.version, &build.os, &build.arch, &dir)
+                    .is_some_and(|entry| {
+                        let has_binary = entry.files
+                            .iter()
+                            .any(|file| file.path == binary);
+
+                        entry.is_intact() && (has_binary || !renamed)
+                    });
+
+                if installed {
+                    messages.already_installed(product, &builds.version);
+
+                    return Ok(());
+                }
+            },
+            Err(e) => messages.manifest_failed(&e),
+        }
+    }
+
     // Download SHASUMS file
     let shasums = client.get_shasums(&builds).await?;
 
+    // Skipping both checks leaves nothing vouching for the download.
+    let no_checksum = matches.get_flag("NO_VERIFY_CHECKSUM");
+    let no_sig      = matches.get_flag("NO_VERIFY_SIGNATURE");
+
+    if no_checksum && no_sig {
+        messages.verification_disabled(product);
+    }
+
+    // Some products and older versions don't publish signatures, which are
+    // only skipped when asked to.
+    if !no_sig && builds.url_shasums_signatures.is_empty() {
+        messages.no_signatures(product, &builds.version);
+
+        bail!(ClientError::NoSignatures);
+    }
+
     // Verify the SHASUMS file against its signature
-    let no_sig = matches.get_flag("NO_VERIFY_SIGNATURE");
     if !no_sig {
         let shasums_filename = builds.url_shasums
             .path_segments()
             .unwrap()
-            .last()
+            .next_back()
             .unwrap();
         messages.verifying_signature(shasums_filename);
 
-        // Download signature file
-        let signature = client.get_signature(&builds).await?;
+        // Without an embedded key, we may need to fetch it, unless the user
+        // gave us one.
+        #[cfg(not(feature = "embed_gpg_key"))]
+        if !matches.contains_id("GPG_KEY") {
+            client.ensure_gpg_key().await?;
+        }
+
+        // Download all of the signature files, any one of them verifying is
+        // enough.
+        let signatures = client.get_signatures(&builds).await?;
+
+        let mut verified = Err(SignatureError::Verification);
 
-        match signature.check(&shasums) {
-            Ok(()) => {
-                let url = builds.shasums_signature_url();
+        for (url, signature) in &signatures {
+            match signature.check(&shasums) {
+                Ok(report) => {
+                    verified = Ok((report, url));
+                    break;
+                },
+                // A signature made by the wrong key is a more useful error
+                // than one that didn't verify at all.
+                Err(err @ SignatureError::KeyMismatch { .. }) => {
+                    verified = Err(err);
+                },
+                Err(_) => {},
+            }
+        }
+
+        match verified {
+            Ok((report, url)) => {
                 let signature_filename = url
                     .path_segments()
                     .unwrap()
-                    .last()
+                    .next_back()
                     .unwrap();
 
-                messages.signature_verification_success(signature_filename);
+                messages.signature_verification_success(
+                    signature_filename,
+                    &report,
+                );
+
+                // A bad local clock doesn't stop verification, but is worth
+                // knowing about.
+                let skew = report.clock_skew(
+                    chrono::Utc::now(),
+                    signature::DEFAULT_MAX_CLOCK_SKEW,
+                );
+
+                if let Some(skew) = skew {
+                    messages.clock_skew(&skew);
+                }
             },
-            Err(e) => {
-                messages.signature_verification_failed(&e);
+            Err(err) => {
+                messages.signature_verification_failed(&err);
 
-                exit(1);
+                bail!(err);
             },
         };
     }
@@ -127,33 +945,139 @@ async fn main() -> Result<()> {
     let filename     = download_url
         .path_segments()
         .unwrap()
-        .last()
+        .next_back()
         .unwrap();
 
-    // Get a new tmpfile for the download.
-    let mut tmpfile = TmpFile::new(filename)?;
+    // Checksum only, print the verified SHA256 of the build and stop before
+    // downloading.
+    if matches.get_flag("PRINT_CHECKSUM") {
+        let shasum = shasums.for_build(build)?
+            .ok_or_else(|| ShasumsError::NoShasumForFile(filename.to_string()))?;
+
+        messages.print_checksum(shasum);
 
-    messages.downloading(filename);
-    client.download(download_url.clone(), &mut tmpfile).await?;
+        return Ok(());
+    }
+
+    // Dry run, report what we would have done and stop before downloading.
+    if matches.get_flag("DRY_RUN") {
+        let shasum = shasums.shasum(filename)?
+            .ok_or_else(|| ShasumsError::NoShasumForFile(filename.to_string()))?;
+
+        // A dry run mustn't create the install dir.
+        let install_dir = if matches.get_flag("DOWNLOAD_ONLY") {
+            None
+        }
+        else if matches.get_flag("VERSIONED") {
+            Some(find_install_dir(matches)?.join(product).join(&builds.version))
+        }
+        else {
+            Some(find_install_dir(matches)?)
+        };
 
-    // Ensure the SHASUM is correct
-    match shasums.check(&mut tmpfile)? {
-        shasums::Checksum::OK  => messages.checksum_ok(filename),
-        shasums::Checksum::Bad => {
-            messages.checksum_bad(filename);
+        messages.dry_run(download_url, shasum, install_dir.as_deref());
 
-            exit(1);
+        return Ok(());
+    }
+
+    // Kept zips go in the current dir, unless a --keep-dir was given. We
+    // validated this in the CLI so we know this is good.
+    let keep_dir = matches
+        .get_one::<PathBuf>("KEEP_DIR")
+        .map(PathBuf::as_path);
+
+    // A cached zip is only used if it matches the shasums, otherwise we
+    // download it again. Like a download, its SHA256 is calculated as it's
+    // loaded, so it isn't read again to check it.
+    let cached = match client.cached_build(&builds, build, filename)? {
+        Some(mut tmpfile) => {
+            match shasums.check(&mut tmpfile)? {
+                shasums::Checksum::Ok => Some(tmpfile),
+                shasums::Checksum::Mismatch { .. }
+                | shasums::Checksum::Missing => None,
+            }
         },
+        None => None,
     };
 
+    let mut tmpfile = if let Some(tmpfile) = cached {
+        messages.cache_hit(filename);
+        messages.checksum_ok(filename);
+
+        tmpfile
+    }
+    else {
+        // Get a new tmpfile for the download. This is created where the
+        // download will end up, the keep dir for --download-only or the
+        // install dir otherwise, so persisting it is a rename on the same
+        // filesystem and a potentially small system temp dir is avoided.
+        // There, an interrupted download can also be resumed by a later run.
+        let tmp_dir = if matches.get_flag("DOWNLOAD_ONLY") {
+            Some(keep_dir.map_or_else(|| PathBuf::from("."), Path::to_path_buf))
+        }
+        else {
+            install_dir(matches).ok().filter(|dir| dir.is_dir())
+        };
+
+        let mut tmpfile = match tmp_dir {
+            Some(dir) => TmpFile::resumable_in(filename, &dir)?,
+            None      => TmpFile::new(filename)?,
+        };
+
+        messages.downloading(filename);
+        let size = client.download(download_url.clone(), &mut tmpfile).await?;
+        messages.downloaded(filename, size);
+
+        // Ensure the SHASUM is correct, unless we've been told not to.
+        // Unverified downloads are never cached.
+        if no_checksum {
+            messages.checksum_skipped(filename);
+        }
+        else {
+            match shasums.check(&mut tmpfile)? {
+                shasums::Checksum::Ok => messages.checksum_ok(filename),
+                shasums::Checksum::Mismatch { expected, actual } => {
+                    messages.checksum_bad(filename, &expected, &actual);
+
+                    bail!(ClientError::BadChecksum {
+                        path: filename.into(),
+                        expected,
+                        actual,
+                    });
+                },
+                shasums::Checksum::Missing => {
+                    bail!("couldn't find {filename} to check its SHA256");
+                },
+            };
+
+            client.cache_build(&builds, build, &mut tmpfile)?;
+        }
+
+        tmpfile
+    };
+
+    // Record the SHA256 we verified the zip against, now that both the
+    // signature and checksum have passed.
+    if let Some(path) = matches.get_one::<PathBuf>("CHECKSUM_FILE") {
+        let shasum = shasums.shasum(filename)?
+            .ok_or_else(|| ShasumsError::NoShasumForFile(filename.to_string()))?;
+
+        fs::write(path, format!("{shasum}  {filename}\n"))
+            .with_context(|| {
+                format!("couldn't write '{}'", path.display())
+            })?;
+
+        messages.checksum_written(path);
+    }
+
     // If we're DOWNLOAD_ONLY (implies KEEP), just persist the file and
     // we're done.
     if matches.get_flag("DOWNLOAD_ONLY") {
-        messages.download_only(filename);
+        let path = tmpfile.persist(keep_dir)?;
 
-        tmpfile.persist()?;
+        messages.download_only(&path);
 
-        exit(0);
+        return Ok(());
     }
 
     // Work out if what we downloaded is installable. This is a crude check to
@@ -161,48 +1085,259 @@ async fn main() -> Result<()> {
     let installable = os == cli::DEFAULT_OS;
     if !installable {
         messages.os_mismatch(cli::DEFAULT_OS, os);
-        messages.skipped_install(filename);
 
-        tmpfile.persist()?;
+        let path = tmpfile.persist(keep_dir)?;
 
-        exit(0);
+        messages.skipped_install(&path);
+
+        return Ok(());
     }
 
     // Continue to attempt installation
-    // Try to get an install_dir
-    let bin_dir = if let Some(dir) = matches.get_one::<PathBuf>("INSTALL_DIR") {
-        // If a --install-dir was given, use that. We validated this in the
-        // CLI so we know this is good.
-        dir.clone()
-    }
-    else {
-        install::bin_dir()?
-    };
+    let bin_dir = install_dir(matches)?;
 
     messages.unzipping(filename, &bin_dir);
 
     let mut zip_handle = tmpfile.handle()?;
 
-    match install::install(&mut zip_handle, &bin_dir) {
-        Ok(extracted_files) => {
-            for file in extracted_files {
-                messages.extracted_file(&file, &bin_dir);
+    let installed = if matches.get_flag("VERSIONED") {
+        install::install_versioned(
+            &mut zip_handle,
+            &bin_dir,
+            product,
+            &builds.version,
+        )
+        .map(|installed| {
+            for file in &installed.files {
+                let file = file.strip_prefix(&installed.dir).unwrap_or(file);
+
+                messages.extracted_file(file, &installed.dir);
             }
 
+            match &installed.current {
+                Some(link) => messages.current_symlink(link, &installed.dir),
+                None       => messages.symlink_unsupported(),
+            }
+
+            if matches.contains_id("USE") {
+                messages.switched(
+                    product,
+                    previous.as_deref(),
+                    &builds.version,
+                    installed.current.as_deref(),
+                );
+            }
+
+            (installed.dir, installed.files)
+        })
+    }
+    else if let Some(name) = matches.get_one::<String>("RENAME") {
+        install::install_renamed(&mut zip_handle, &bin_dir, product, name)
+            .map(|entries| {
+                let files = entries
+                    .iter()
+                    .map(|entry| {
+                        messages.extracted_file(&entry.filename, &bin_dir);
+
+                        bin_dir.join(&entry.filename)
+                    })
+                    .collect();
+
+                let binary = entries
+                    .iter()
+                    .find(|entry| entry.kind == EntryKind::Binary);
+
+                if let Some(binary) = binary {
+                    messages.renamed(product, &bin_dir.join(&binary.filename));
+                }
+
+                (bin_dir.clone(), files)
+            })
+    }
+    else if matches.get_flag("PRESERVE_STRUCTURE") {
+        install::install_preserving(&mut zip_handle, &bin_dir)
+            .and_then(|extracted_files| {
+                // The binary may be nested anywhere in the tree.
+                let binaries = extracted_files
+                    .iter()
+                    .filter(|entry| {
+                        entry.filename.file_name() == Some(product.as_ref())
+                    });
+
+                for entry in binaries {
+                    install::ensure_executable(&bin_dir.join(&entry.filename))?;
+                }
+
+                Ok(extracted_files)
+            })
+            .map(|extracted_files| {
+                for entry in &extracted_files {
+                    messages.extracted_file(&entry.filename, &bin_dir);
+                }
+
+                let files = extracted_files
+                    .iter()
+                    .map(|entry| bin_dir.join(&entry.filename))
+                    .collect();
+
+                (bin_dir.clone(), files)
+            })
+    }
+    else if matches.get_flag("INSTALL_EXTRAS") {
+        install::install_classified(&mut zip_handle, &bin_dir, product, true)
+            .map(|entries| {
+                let files = entries
+                    .iter()
+                    .filter(|entry| entry.installed)
+                    .map(|entry| {
+                        messages.extracted_file(&entry.filename, &bin_dir);
+
+                        bin_dir.join(&entry.filename)
+                    })
+                    .collect();
+
+                (bin_dir.clone(), files)
+            })
+    }
+    else {
+        install::install(&mut zip_handle, &bin_dir)
+            .and_then(|extracted_files| {
+                let binary = bin_dir.join(product);
+                if binary.is_file() {
+                    install::ensure_executable(&binary)?;
+                }
+
+                Ok(extracted_files)
+            })
+            .map(|extracted_files| {
+                for entry in &extracted_files {
+                    messages.extracted_file(&entry.filename, &bin_dir);
+                }
+
+                let files = extracted_files
+                    .iter()
+                    .map(|entry| bin_dir.join(&entry.filename))
+                    .collect();
+
+                (bin_dir.clone(), files)
+            })
+    };
+
+    let dir = match installed {
+        Ok((dir, files)) => {
             messages.installation_successful();
+
+            // Failing to record the install doesn't undo it, so we only warn.
+            let recorded = ManifestEntry::new(
+                product,
+                &builds.version,
+                &build.os,
+                &build.arch,
+                &dir,
+                &files,
+            )
+            .and_then(install::record_install);
+
+            if let Err(e) = recorded {
+                messages.manifest_failed(&e);
+            }
+
+            // Products installed outside of the PATH can't be run by name,
+            // which otherwise looks like the install silently failed.
+            if !install::path::is_in_path(&bin_dir) {
+                ensure_path(messages, matches, &bin_dir);
+            }
+
+            dir
         },
         Err(e) => {
             messages.installation_failed(&e);
 
-            exit(1);
+            bail!(e);
+        }
+    };
+
+    // A --keep-dir implies --keep.
+    if matches.get_flag("KEEP") || keep_dir.is_some() {
+        let path = tmpfile.persist(keep_dir)?;
+
+        messages.keep_zipfile(&path);
+    }
+
+    // Stop Gatekeeper prompting before the product is first run, and check
+    // that its code signature is intact. Neither stops the install.
+    #[cfg(target_os = "macos")]
+    if !matches.get_flag("NO_GATEKEEPER_FIX") {
+        let binary = dir.join(binary_name(matches, product));
+
+        if binary.is_file() {
+            gatekeeper_fix(messages, &binary);
         }
     }
 
-    if matches.get_flag("KEEP") {
-        messages.keep_zipfile(filename);
+    // Optionally check that the installed product runs and reports the
+    // version we installed. Products for another OS never get this far.
+    if matches.get_flag("VERIFY_EXEC") {
+        let binary = dir.join(binary_name(matches, product));
+
+        // Waiting on the product would otherwise block the runtime.
+        let exec_binary  = binary.clone();
+        let exec_version = builds.version.clone();
+
+        tokio::task::spawn_blocking(move || {
+            install::verify_exec(&exec_binary, &exec_version, VERIFY_EXEC_TIMEOUT)
+        })
+        .await??;
 
-        tmpfile.persist()?;
+        messages.verified_exec(&binary, &builds.version);
     }
 
     Ok(())
 }
+
+// Clear the quarantine attribute of the installed `binary` and verify its
+// code signature, only warning about any failures.
+#[cfg(target_os = "macos")]
+fn gatekeeper_fix(messages: &Messages, binary: &Path) {
+    match install::clear_quarantine(binary) {
+        Ok(true)  => messages.quarantine_cleared(binary),
+        Ok(false) => {},
+        Err(e)    => messages.gatekeeper_failed(&e),
+    }
+
+    match install::verify_codesign(binary) {
+        Ok(()) => messages.codesign_verified(binary),
+        Err(e) => messages.gatekeeper_failed(&e),
+    }
+}
+
+// The filename of the installed `product` binary, taking any --rename into
+// account. As with the install, an `.exe` suffix is kept on Windows.
+fn binary_name(matches: &ArgMatches, product: &str) -> String {
+    match matches.get_one::<String>("RENAME") {
+        Some(name) if name.ends_with(EXE_SUFFIX) => name.clone(),
+        Some(name) => format!("{name}{EXE_SUFFIX}"),
+        None       => format!("{product}{EXE_SUFFIX}"),
+    }
+}
+
+// Explain a failure to get the given `version` of `product`, telling a
+// version that doesn't exist apart from a server failing. The ClientError is
+// kept in the chain for the exit code.
+fn version_error(
+    err: ClientError,
+    product: &str,
+    version: &str,
+) -> anyhow::Error {
+    let context = match &err {
+        ClientError::NotFound(_) => {
+            format!("no such version {version} of {product}")
+        },
+        ClientError::HttpStatus { status: 500..=599, .. } => {
+            format!("server error getting {product} {version}")
+        },
+        _ => return err.into(),
+    };
+
+    anyhow::Error::new(err).context(context)
+}
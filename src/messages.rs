@@ -1,43 +1,180 @@
 // Messages output by other parts of the program
 #![forbid(unsafe_code)]
 #![forbid(missing_docs)]
+use hcdl::client::{
+    ClientEvent,
+    EffectiveConfig,
+    HealthReport,
+    UpgradeStatus,
+};
+use hcdl::client::health::HealthStatus;
+use hcdl::client::build::Build;
+use hcdl::client::product_version::ProductVersion;
 use hcdl::error::{
+    ClientError,
     InstallError,
     SignatureError,
 };
-use std::path::Path;
+use hcdl::install::Manifest;
+use hcdl::signature::{
+    ClockSkew,
+    VerificationReport,
+};
+use serde_json::{
+    json,
+    Value,
+};
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::time::Duration;
+use url::Url;
+
+/// The format that [`Messages`] are output in.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OutputFormat {
+    /// Human readable text.
+    #[default]
+    Human,
+
+    /// JSON objects, one per line, for key events only.
+    Json,
+}
+
+impl From<&str> for OutputFormat {
+    fn from(format: &str) -> Self {
+        match format {
+            "json" => Self::Json,
+            _      => Self::Human,
+        }
+    }
+}
 
 /// Handler for the various message we need to output.
+#[derive(Clone)]
 pub struct Messages {
-    quiet: bool,
+    format: OutputFormat,
+    quiet:  bool,
 }
 
 impl Messages {
     /// Crates a new [`Messages`] handler. If `quiet` is set to `true`, no
     /// output will be given.
     #[must_use]
-    pub fn new(quiet: bool) -> Self {
+    pub fn new(quiet: bool, format: OutputFormat) -> Self {
         Self {
+            format,
             quiet,
         }
     }
 
+    // Human readable output, only given in the human output format.
     fn stdout(&self, msg: &str) {
-        if !self.quiet {
+        if !self.quiet && self.format == OutputFormat::Human {
             println!("{msg}");
         }
     }
 
+    // Structured output, only given in the JSON output format.
+    fn json(&self, value: &Value) {
+        if !self.quiet && self.format == OutputFormat::Json {
+            println!("{value}");
+        }
+    }
+
     #[allow(clippy::unused_self)]
     fn stderr(&self, msg: &str) {
         eprintln!("{msg}");
     }
 
+    /// Output for events emitted by the [`hcdl::client::Client`].
+    pub fn client_event(&self, event: &ClientEvent) {
+        match event {
+            ClientEvent::GpgKeyFetched { fingerprint, path } => {
+                self.gpg_key_fetched(fingerprint, path);
+            },
+            ClientEvent::Mirror { url } => self.mirror_used(url),
+            ClientEvent::Retry { attempt, retries, delay, error } => {
+                self.retrying(*attempt, *retries, *delay, error);
+            },
+        }
+    }
+
+    /// Output the upgrade status of every installed product.
+    pub fn check_all(&self, statuses: &[UpgradeStatus]) {
+        if statuses.is_empty() {
+            self.stdout("No products installed.");
+        }
+
+        for status in statuses {
+            let msg = if status.outdated {
+                format!(
+                    "{product}: {installed} -> {latest} (upgrade available)",
+                    product   = status.product,
+                    installed = status.installed,
+                    latest    = status.latest,
+                )
+            }
+            else {
+                format!(
+                    "{product}: {installed} (up to date)",
+                    product   = status.product,
+                    installed = status.installed,
+                )
+            };
+
+            self.stdout(&msg);
+        }
+
+        self.json(&json!({
+            "event":    "check_all",
+            "products": statuses,
+        }));
+    }
+
+    /// Output when the latest version of an installed `product` couldn't be
+    /// checked.
+    pub fn check_failed(&self, product: &str, error: &ClientError) {
+        let msg = format!("Failed to check {product} for upgrades: {error}");
+
+        self.stderr(&msg);
+        self.json(&json!({
+            "event":   "check_failed",
+            "product": product,
+            "error":   error.to_string(),
+        }));
+    }
+
     /// Output when the checksum of the file is bad.
-    pub fn checksum_bad(&self, filename: &str) {
-        let msg = format!("SHA256 of {filename} did not match.");
+    pub fn checksum_bad(&self, filename: &str, expected: &str, actual: &str) {
+        let msg = format!(
+            "SHA256 of {filename} did not match.\nExpected: {expected}\nActual:   {actual}",
+        );
 
         self.stderr(&msg);
+        self.json(&json!({
+            "event":    "checksum",
+            "filename": filename,
+            "ok":       false,
+            "expected": expected,
+            "actual":   actual,
+        }));
+    }
+
+    /// Output when checking the SHA256 of a download is skipped with
+    /// `--no-verify-checksum`.
+    pub fn checksum_skipped(&self, filename: &str) {
+        let msg = format!(
+            "WARNING: SHA256 of {filename} NOT verified, --no-verify-checksum was given.",
+        );
+
+        self.stderr(&msg);
+        self.json(&json!({
+            "event":    "checksum",
+            "filename": filename,
+            "skipped":  true,
+        }));
     }
 
     /// Output when the checksum of the file is good.
@@ -45,6 +182,142 @@ impl Messages {
         let msg = format!("SHA256 of {filename} OK.");
 
         self.stdout(&msg);
+        self.json(&json!({
+            "event":    "checksum",
+            "filename": filename,
+            "ok":       true,
+        }));
+    }
+
+    /// Output the changelog for a product version.
+    pub fn changelog(&self, product: &str, version: &str, changelog: &str) {
+        self.stdout(changelog);
+        self.json(&json!({
+            "event":     "changelog",
+            "product":   product,
+            "version":   version,
+            "changelog": changelog,
+        }));
+    }
+
+    /// Output when shell completions have been installed.
+    #[cfg(feature = "shell_completion")]
+    pub fn completions_installed(&self, shell: &str, path: &Path) {
+        let msg = format!("Installed {shell} completions to '{}'.", path.display());
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event": "completions_installed",
+            "shell": shell,
+            "path":  path,
+        }));
+    }
+
+    /// Output the actions a dry run would have taken.
+    pub fn dry_run(&self, url: &Url, shasum: &str, install_dir: Option<&Path>) {
+        let install_dir_msg = match install_dir {
+            Some(dir) => dir.display().to_string(),
+            None      => "none, download only".to_string(),
+        };
+
+        let msg = format!(
+            "Dry run, would download: {url}\nExpected SHA256: {shasum}\nInstall dir: {install_dir_msg}",
+        );
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event":       "dry_run",
+            "url":         url.as_str(),
+            "sha256":      shasum,
+            "install_dir": install_dir,
+        }));
+    }
+
+    /// Output the install dir. This is the requested output rather than a
+    /// status message, so it's given regardless of quiet mode and the output
+    /// format.
+    #[allow(clippy::unused_self)]
+    pub fn print_install_dir(&self, dir: &Path) {
+        println!("{}", dir.display());
+    }
+
+    /// Output only the SHA256 of a build, for use in scripts.
+    pub fn print_checksum(&self, shasum: &str) {
+        println!("{shasum}");
+    }
+
+    /// Output when the requested product version is already installed.
+    pub fn already_installed(&self, product: &str, version: &str) {
+        let msg = format!("{product} v{version} is already installed.");
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event":   "already_installed",
+            "product": product,
+            "version": version,
+        }));
+    }
+
+    /// Output when a product name is ambiguous.
+    pub fn ambiguous_product(&self, name: &str, candidates: &[&str]) {
+        let msg = format!(
+            "Product '{name}' is ambiguous, did you mean one of: {candidates}",
+            candidates = candidates.join(", "),
+        );
+
+        self.stderr(&msg);
+        self.json(&json!({
+            "event":      "ambiguous_product",
+            "name":       name,
+            "candidates": candidates,
+        }));
+    }
+
+    /// Output when a product name is assumed to mean a known product.
+    pub fn assumed_product(&self, name: &str, product: &str) {
+        let msg = format!("Product '{name}' not found, assuming {product}.");
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event":   "assumed_product",
+            "name":    name,
+            "product": product,
+        }));
+    }
+
+    /// Output when the --deadline expires.
+    pub fn deadline_exceeded(&self, deadline: Duration) {
+        let msg = format!(
+            "Deadline of {secs}s exceeded, giving up.",
+            secs = deadline.as_secs(),
+        );
+
+        self.stderr(&msg);
+        self.json(&json!({
+            "event":    "deadline_exceeded",
+            "deadline": deadline.as_secs(),
+        }));
+    }
+
+    /// Output when a build for a compatible arch is used instead of the
+    /// requested arch.
+    pub fn build_fallback(&self, os: &str, arch: &str, fallback_arch: &str) {
+        let msg = format!(
+            "Warning: no build for {os}-{arch}, using {os}-{fallback_arch} instead.",
+        );
+
+        self.stderr(&msg);
+    }
+
+    /// Output when a cached file is used instead of downloading it.
+    pub fn cache_hit(&self, filename: &str) {
+        let msg = format!("Using cached {filename}, skipping download.");
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event":    "cache_hit",
+            "filename": filename,
+        }));
     }
 
     /// Output when the download of a file is starting.
@@ -52,14 +325,131 @@ impl Messages {
         let msg = format!("Downloading {filename}...");
 
         self.stdout(&msg);
+        self.json(&json!({
+            "event":    "downloading",
+            "filename": filename,
+        }));
+    }
+
+    /// Output when the download of a file has finished. The progress bar
+    /// already shows this in human output.
+    pub fn downloaded(&self, filename: &str, size: u64) {
+        self.json(&json!({
+            "event":    "downloaded",
+            "filename": filename,
+            "size":     size,
+        }));
+    }
+
+    /// Output when the `current` symlink of a versioned install has been
+    /// updated.
+    pub fn current_symlink(&self, link: &Path, target: &Path) {
+        let msg = format!(
+            "-> Pointed '{link}' at '{target}'",
+            link = link.display(),
+            target = target.display(),
+        );
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event":  "symlink",
+            "path":   link,
+            "target": target,
+        }));
     }
 
     /// Output when download only mode is used to indicate the downloaded file
     /// will not be deleted.
-    pub fn download_only(&self, filename: &str) {
-        let msg = format!("Download only mode, keeping {filename}.");
+    pub fn download_only(&self, path: &Path) {
+        let msg = format!("Download only mode, kept {}.", path.display());
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event": "kept",
+            "path":  path,
+        }));
+    }
+
+    /// Output when the verified SHA256 of a download was written to a
+    /// checksum file.
+    pub fn checksum_written(&self, path: &Path) {
+        let msg = format!("Wrote SHA256 to {}.", path.display());
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event": "checksum_file",
+            "path":  path,
+        }));
+    }
+
+    /// Output when a product version doesn't publish any shasums
+    /// signatures, so it can't be verified.
+    pub fn no_signatures(&self, product: &str, version: &str) {
+        let msg = format!(
+            "{product} {version} doesn't publish signatures for its SHA256 sums, use --no-verify-signature to install it without verifying them.",
+        );
+
+        self.stderr(&msg);
+        self.json(&json!({
+            "event":   "no_signatures",
+            "product": product,
+            "version": version,
+        }));
+    }
+
+    /// Output to remind the user that the install directory needs to be in
+    /// their PATH, with the `export` line that adds it for their shell if
+    /// it's known.
+    pub fn ensure_in_path(&self, dir: &Path, export: Option<&str>) {
+        let msg = format!(
+            "Ensure that '{dir}' is in your PATH to use the installed product.",
+            dir = dir.display(),
+        );
+
+        self.stdout(&msg);
+
+        if let Some(export) = export {
+            let msg = format!(
+                "Add this to your shell's rc file, or run hcdl with --ensure-path:\n  {export}",
+            );
+
+            self.stdout(&msg);
+        }
+    }
+
+    /// Output when the install directory was added to the PATH in a shell rc
+    /// file, or was already added there by an earlier run.
+    pub fn path_updated(&self, dir: &Path, rc_file: &Path, changed: bool) {
+        let msg = if changed {
+            format!(
+                "Added '{dir}' to your PATH in '{rc_file}', start a new shell to use the installed product.",
+                dir     = dir.display(),
+                rc_file = rc_file.display(),
+            )
+        }
+        else {
+            format!(
+                "'{dir}' is already added to your PATH in '{rc_file}', start a new shell to use the installed product.",
+                dir     = dir.display(),
+                rc_file = rc_file.display(),
+            )
+        };
 
         self.stdout(&msg);
+        self.json(&json!({
+            "event":   "path_updated",
+            "dir":     dir,
+            "rc_file": rc_file,
+            "changed": changed,
+        }));
+    }
+
+    /// Output when the install directory couldn't be added to the PATH in a
+    /// shell rc file.
+    pub fn path_update_failed(&self, error: &InstallError) {
+        let msg = format!("Warning: couldn't add the install dir to your PATH: {error}");
+
+        self.stderr(&msg);
     }
 
     /// Output when a file is being extracted.
@@ -71,6 +461,10 @@ impl Messages {
         );
 
         self.stdout(&msg);
+        self.json(&json!({
+            "event": "extracted",
+            "path":  dest.join(filename),
+        }));
     }
 
     /// Output when we can't find a product build for the specified OS and
@@ -81,34 +475,110 @@ impl Messages {
         self.stderr(&msg);
     }
 
+    /// Output when the HashiCorp GPG key has been fetched.
+    pub fn gpg_key_fetched(&self, fingerprint: &str, path: &Path) {
+        let msg = format!(
+            "Fetched HashiCorp GPG key with fingerprint {fingerprint} to \
+             '{path}'. Verify this against \
+             https://www.hashicorp.com/security.",
+            path = path.display(),
+        );
+
+        self.stdout(&msg);
+    }
+
+    /// Output when the HashiCorp GPG key was refreshed, after the fetched key
+    /// itself has been reported, with where the previous key was backed up
+    /// to if there was one.
+    pub fn gpg_key_refreshed(&self, fingerprint: &str, backup: Option<&Path>) {
+        if let Some(backup) = backup {
+            let msg = format!(
+                "Backed up the previous GPG key to '{backup}'.",
+                backup = backup.display(),
+            );
+
+            self.stdout(&msg);
+        }
+
+        self.json(&json!({
+            "event":       "gpg_key_refreshed",
+            "fingerprint": fingerprint,
+            "backup":      backup,
+        }));
+    }
+
     /// Output when a product installation has failed.
     pub fn installation_failed(&self, error: &InstallError) {
         let msg = format!("Installation failed with error: {error}");
 
         self.stderr(&msg);
+        self.json(&json!({
+            "event": "install",
+            "ok":    false,
+            "error": error.to_string(),
+        }));
     }
 
     /// Output when a product installation was successful.
     pub fn installation_successful(&self) {
         self.stdout("Installation successful.");
+        self.json(&json!({
+            "event": "install",
+            "ok":    true,
+        }));
     }
 
     /// Output when a zipfile has been kept instead of being deleted.
-    pub fn keep_zipfile(&self, filename: &str) {
-        let msg = format!("Keeping zipfile {filename} in current directory.");
+    pub fn keep_zipfile(&self, path: &Path) {
+        let msg = format!("Kept zipfile {}.", path.display());
 
         self.stdout(&msg);
+        self.json(&json!({
+            "event": "kept",
+            "path":  path,
+        }));
     }
 
     /// Output when checking for the latest product version.
-    pub fn latest_version(&self, latest: &str) {
+    pub fn latest_version(&self, latest: &ProductVersion) {
         let msg = format!("Latest version: {latest}");
 
         self.stdout(&msg);
+        self.json(&json!({
+            "event":     "latest_version",
+            "product":   latest.name,
+            "version":   latest.version,
+            "timestamp": latest.timestamp_updated.to_rfc3339(),
+        }));
+    }
+
+    /// Output the builds of a product version, as a table.
+    pub fn list_builds(&self, product: &str, version: &str, builds: &[Build]) {
+        let os_width   = column_width("OS", builds.iter().map(|b| b.os.as_str()));
+        let arch_width = column_width("ARCH", builds.iter().map(|b| b.arch.as_str()));
+
+        let mut msg = format!("{:os_width$}  {:arch_width$}  URL", "OS", "ARCH");
+
+        for build in builds {
+            msg.push_str(&format!(
+                "\n{:os_width$}  {:arch_width$}  {}",
+                build.os,
+                build.arch,
+                build.url,
+            ));
+        }
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event":   "builds",
+            "product": product,
+            "version": version,
+            "builds":  builds,
+        }));
     }
 
     /// Output when the product list was requested.
-    pub fn list_products(&self, products: &[&str]) {
+    pub fn list_products(&self, products: &[String]) {
         let msg = format!(
             "Products: {products}",
             products = products.join(", "),
@@ -117,6 +587,44 @@ impl Messages {
         self.stdout(&msg);
     }
 
+    /// Output when discovering products from the releases API fails.
+    pub fn product_discovery_failed(&self, error: &ClientError) {
+        let msg = format!(
+            "Warning: couldn't discover products, using the known list: {error}",
+        );
+
+        self.stderr(&msg);
+    }
+
+    /// Output when the cached list of known products has been refreshed.
+    pub fn products_refreshed(&self, count: usize, path: &Path) {
+        let msg = format!(
+            "Refreshed {count} known products in '{}'.",
+            path.display(),
+        );
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event": "products_refreshed",
+            "count": count,
+            "path":  path,
+        }));
+    }
+
+    /// Output when unknown products are requested.
+    pub fn unknown_products(&self, products: &[&str]) {
+        let msg = format!(
+            "Unknown products: {products}",
+            products = products.join(", "),
+        );
+
+        self.stderr(&msg);
+        self.json(&json!({
+            "event":    "unknown_products",
+            "products": products,
+        }));
+    }
+
     /// Output when an installation is attempted for a product OS that doesn't
     /// match the current OS.
     pub fn os_mismatch(&self, os: &str, requested: &str) {
@@ -127,28 +635,359 @@ impl Messages {
         self.stdout(&msg);
     }
 
+    /// Output the fingerprint of the GPG key used to verify signatures.
+    pub fn gpg_fingerprint(&self, fingerprint: &str) {
+        let msg = format!("GPG key fingerprint: {fingerprint}");
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event":       "gpg_fingerprint",
+            "fingerprint": fingerprint,
+        }));
+    }
+
+    /// Output the files removed when uninstalling a product.
+    pub fn uninstalled(&self, product: &str, removed: &[PathBuf]) {
+        for path in removed {
+            let msg = format!("Removed {}", path.display());
+
+            self.stdout(&msg);
+        }
+
+        self.json(&json!({
+            "event":   "uninstall",
+            "product": product,
+            "removed": removed,
+        }));
+    }
+
+    /// Output the installed products manifest.
+    pub fn installed(&self, manifest: &Manifest) {
+        for entry in &manifest.installed {
+            let msg = format!(
+                "{product} v{version} ({os}-{arch}) in {dir}, installed {installed_at}",
+                product      = entry.product,
+                version      = entry.version,
+                os           = entry.os,
+                arch         = entry.arch,
+                dir          = entry.dir.display(),
+                installed_at = entry.installed_at,
+            );
+
+            self.stdout(&msg);
+        }
+
+        self.json(&json!({
+            "event":     "installed",
+            "installed": manifest.installed,
+        }));
+    }
+
+    /// Output the effective client configuration, one setting per line.
+    pub fn config(&self, config: &EffectiveConfig) {
+        let value = json!(config);
+
+        if let Value::Object(settings) = &value {
+            let width = settings.keys().map(String::len).max().unwrap_or(0);
+
+            for (key, setting) in settings {
+                let setting = match setting {
+                    Value::Null      => "-".to_string(),
+                    Value::String(s) => s.clone(),
+                    _                => setting.to_string(),
+                };
+
+                let msg = format!("{key:<width$}  {setting}");
+
+                self.stdout(&msg);
+            }
+        }
+
+        self.json(&json!({
+            "event":  "config",
+            "config": value,
+        }));
+    }
+
+    /// Output the report of a health check, failed steps are output to
+    /// stderr.
+    pub fn healthcheck(&self, report: &HealthReport) {
+        let version = report.version.as_deref().unwrap_or("unknown version");
+        let msg     = format!(
+            "Health check against {product} {version}:",
+            product = report.product,
+        );
+
+        self.stdout(&msg);
+
+        for check in &report.checks {
+            match &check.status {
+                HealthStatus::Passed => {
+                    self.stdout(&format!("  {step}: ok", step = check.step));
+                },
+                HealthStatus::Failed(error) => {
+                    self.stderr(&format!("  {step}: FAILED: {error}", step = check.step));
+                },
+                HealthStatus::Skipped => {
+                    self.stdout(&format!("  {step}: skipped", step = check.step));
+                },
+            }
+        }
+
+        self.json(&json!({
+            "event":  "healthcheck",
+            "passed": report.passed(),
+            "report": report,
+        }));
+    }
+
+    /// Output when the installed products manifest couldn't be read or
+    /// updated.
+    pub fn manifest_failed(&self, error: &InstallError) {
+        let msg = format!("Warning: installed products manifest: {error}");
+
+        self.stderr(&msg);
+    }
+
+    /// Output when content was fetched from a mirror.
+    pub fn mirror_used(&self, url: &Url) {
+        let msg = format!("Fetched {url} from mirror.");
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event": "mirror",
+            "url":   url,
+        }));
+    }
+
+    /// Output when a signature's creation time suggests that the local clock
+    /// is wrong.
+    pub fn clock_skew(&self, skew: &ClockSkew) {
+        let (kind, secs, msg) = match skew {
+            ClockSkew::Future(ahead) => (
+                "future",
+                ahead.as_secs(),
+                format!(
+                    "Warning: signature appears to be created {hours}h in the future, check the system clock.",
+                    hours = ahead.as_secs() / 3600,
+                ),
+            ),
+            ClockSkew::Old(age) => (
+                "old",
+                age.as_secs(),
+                format!(
+                    "Warning: signature appears to be created {days} days ago, check the system clock.",
+                    days = age.as_secs() / 86400,
+                ),
+            ),
+        };
+
+        self.stderr(&msg);
+        self.json(&json!({
+            "event": "clock_skew",
+            "skew":  kind,
+            "secs":  secs,
+        }));
+    }
+
+    /// Output the name a product binary was installed as with `--rename`.
+    pub fn renamed(&self, product: &str, path: &Path) {
+        let msg = format!("Installed {product} as '{}'.", path.display());
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event":   "renamed",
+            "product": product,
+            "path":    path,
+        }));
+    }
+
+    /// Output when the code signature of an installed product was verified
+    /// on macOS.
+    #[cfg(target_os = "macos")]
+    pub fn codesign_verified(&self, path: &Path) {
+        let msg = format!("Code signature of '{}' verified.", path.display());
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event": "codesign_verified",
+            "path":  path,
+        }));
+    }
+
+    /// Output when clearing the quarantine attribute of, or verifying the
+    /// code signature of, an installed product failed on macOS.
+    #[cfg(target_os = "macos")]
+    pub fn gatekeeper_failed(&self, error: &InstallError) {
+        let msg = format!("Warning: {error}");
+
+        self.stderr(&msg);
+        self.json(&json!({
+            "event": "gatekeeper_failed",
+            "error": error.to_string(),
+        }));
+    }
+
+    /// Output when the quarantine attribute of an installed product was
+    /// cleared on macOS.
+    #[cfg(target_os = "macos")]
+    pub fn quarantine_cleared(&self, path: &Path) {
+        let msg = format!("Cleared quarantine attribute of '{}'.", path.display());
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event": "quarantine_cleared",
+            "path":  path,
+        }));
+    }
+
+    /// Output when a product's version is pinned in the versions file.
+    pub fn pinned_version(&self, product: &str, version: &str, file: &str) {
+        let msg = format!("Using {product} version {version} pinned in {file}.");
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event":   "pinned",
+            "product": product,
+            "version": version,
+            "file":    file,
+        }));
+    }
+
+    /// Output when installing a product has failed.
+    pub fn product_failed(&self, product: &str, error: &anyhow::Error) {
+        let msg = format!("Failed to install {product}: {error:#}");
+
+        self.stderr(&msg);
+        self.json(&json!({
+            "event":   "failed",
+            "product": product,
+            "error":   format!("{error:#}"),
+        }));
+    }
+
+    /// Output when a version constraint has been resolved to a version.
+    pub fn resolved_version(&self, constraint: &str, version: &ProductVersion) {
+        let msg = format!("Resolved '{constraint}' to: {version}");
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event":      "resolved_version",
+            "constraint": constraint,
+            "product":    version.name,
+            "version":    version.version,
+        }));
+    }
+
+    /// Output when a download is being retried after a failure.
+    pub fn retrying(
+        &self,
+        attempt: u32,
+        retries: u32,
+        delay: Duration,
+        error: &ClientError,
+    ) {
+        let msg = format!(
+            "Download failed with error: {error}, retrying in {delay:?} \
+             ({attempt}/{retries})...",
+        );
+
+        self.stdout(&msg);
+    }
+
+    /// Output when the `current` symlink of a versioned install couldn't be
+    /// created because symlinks aren't supported.
+    pub fn symlink_unsupported(&self) {
+        self.stderr(
+            "Symlinks are not supported on this platform, skipped updating \
+             the 'current' symlink.",
+        );
+    }
+
+    /// Output when the `current` symlink of `product` has been switched from
+    /// the `previous` version to `version`.
+    pub fn switched(
+        &self,
+        product: &str,
+        previous: Option<&str>,
+        version: &str,
+        link: Option<&Path>,
+    ) {
+        let msg = match previous {
+            Some(previous) => format!("Switched {product} from {previous} to {version}"),
+            None           => format!("Switched {product} to {version}"),
+        };
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event":    "switch",
+            "product":  product,
+            "previous": previous,
+            "version":  version,
+            "path":     link,
+        }));
+    }
+
+    /// Output when both `--no-verify-signature` and `--no-verify-checksum`
+    /// are given, leaving nothing to verify a download.
+    pub fn verification_disabled(&self, product: &str) {
+        let msg = format!(
+            "WARNING: signature AND checksum verification are disabled, nothing verifies that the {product} download is genuine or intact!",
+        );
+
+        self.stderr(&msg);
+        self.json(&json!({
+            "event":   "verification_disabled",
+            "product": product,
+        }));
+    }
+
     /// Output when signature verification has failed.
     pub fn signature_verification_failed(&self, error: &SignatureError) {
         let msg = format!("Verification failed, error: {error}");
 
         self.stderr(&msg);
+        self.json(&json!({
+            "event": "signature",
+            "ok":    false,
+            "error": error.to_string(),
+        }));
     }
 
     /// Output when signature verification is successful.
-    pub fn signature_verification_success(&self, signature: &str) {
-        let msg = format!("Verified against {signature}.");
+    pub fn signature_verification_success(
+        &self,
+        signature: &str,
+        report: &VerificationReport,
+    ) {
+        let msg = format!(
+            "Verified {signature} against key 0x{key_id}.",
+            key_id = report.short_key_id(),
+        );
 
         self.stdout(&msg);
+        self.json(&json!({
+            "event":       "signature",
+            "ok":          true,
+            "signature":   signature,
+            "key_id":      report.key_id,
+            "fingerprint": report.fingerprint,
+            "created":     report.created.map(|created| created.to_rfc3339()),
+        }));
     }
 
     /// Output when installation of the product is skipped.
-    pub fn skipped_install(&self, filename: &str) {
+    pub fn skipped_install(&self, path: &Path) {
         let msg = format!(
-            "Skipping install and keeping zipfile '{filename}' in current \
-             directory.",
+            "Skipped install and kept zipfile '{}'.",
+            path.display(),
         );
 
         self.stdout(&msg);
+        self.json(&json!({
+            "event": "kept",
+            "path":  path,
+        }));
     }
 
     /// Output when content is being unzipped.
@@ -161,6 +1000,32 @@ impl Messages {
         self.stdout(&msg);
     }
 
+    /// Output when an already downloaded file has been verified.
+    pub fn verified_file(&self, path: &Path) {
+        let msg = format!("Verified {}.", path.display());
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event": "verified",
+            "path":  path,
+            "ok":    true,
+        }));
+    }
+
+    /// Output when an installed product ran and reported the expected
+    /// version.
+    pub fn verified_exec(&self, binary: &Path, version: &str) {
+        let msg = format!("{} reports version {version}.", binary.display());
+
+        self.stdout(&msg);
+        self.json(&json!({
+            "event":   "verified_exec",
+            "path":    binary,
+            "version": version,
+            "ok":      true,
+        }));
+    }
+
     /// Output when a signature is being verified.
     pub fn verifying_signature(&self, shasums: &str) {
         let msg = format!(
@@ -170,3 +1035,8 @@ impl Messages {
         self.stdout(&msg);
     }
 }
+
+// Width of a table column with the given `header` and `values`.
+fn column_width<'a>(header: &str, values: impl Iterator<Item = &'a str>) -> usize {
+    values.map(str::len).fold(header.len(), usize::max)
+}
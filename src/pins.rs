@@ -0,0 +1,109 @@
+// pins: Handle product versions pinned in a versions file
+#![forbid(unsafe_code)]
+#![forbid(missing_docs)]
+use crate::error::PinsError;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The name of the pinned versions file, looked for in the current
+/// directory.
+pub const PINS_FILE: &str = ".hcdl-versions";
+
+/// Product versions pinned in a TOML file mapping product names to versions
+/// or version constraints, e.g.
+///
+/// ```toml
+/// terraform = "~> 1.5"
+/// vault = "1.15.0"
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Pins {
+    versions: BTreeMap<String, String>,
+}
+
+impl Pins {
+    /// Load the [`Pins`] from the file at `path`, returning `None` if the
+    /// file doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Errors if:
+    ///   - The file exists but can't be read
+    ///   - The file isn't a TOML table of product names to version strings
+    pub fn load(path: &Path) -> Result<Option<Self>, PinsError> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok(None);
+            },
+            Err(err) => {
+                return Err(PinsError::Read {
+                    path:   path.to_path_buf(),
+                    source: err,
+                });
+            },
+        };
+
+        let versions = toml::from_str(&content)
+            .map_err(|err| PinsError::Malformed {
+                path:   path.to_path_buf(),
+                source: err,
+            })?;
+
+        let pins = Self {
+            versions: versions,
+        };
+
+        Ok(Some(pins))
+    }
+
+    /// Return the version pinned for `product`, if there is one.
+    #[must_use]
+    pub fn version(&self, product: &str) -> Option<&str> {
+        self.versions.get(product).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_load() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PINS_FILE);
+
+        fs::write(&path, "terraform = \"~> 1.5\"\nvault = \"1.15.0\"\n").unwrap();
+
+        let pins = Pins::load(&path).unwrap().unwrap();
+
+        assert_eq!(pins.version("terraform"), Some("~> 1.5"));
+        assert_eq!(pins.version("vault"), Some("1.15.0"));
+        assert_eq!(pins.version("consul"), None);
+    }
+
+    #[test]
+    fn test_load_missing() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let pins = Pins::load(&dir.path().join(PINS_FILE)).unwrap();
+
+        assert_eq!(pins, None);
+    }
+
+    #[test]
+    fn test_load_malformed() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PINS_FILE);
+
+        // Versions must be strings.
+        fs::write(&path, "terraform = 1.5\n").unwrap();
+
+        let res = Pins::load(&path);
+
+        assert!(matches!(res.unwrap_err(), PinsError::Malformed { .. }));
+    }
+}
@@ -1,14 +1,249 @@
 // products: List of products that hcdl can be used with
 #![forbid(unsafe_code)]
 #![forbid(missing_docs)]
+use anyhow::{
+    Context,
+    Result,
+};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use tempfile::NamedTempFile;
+
+// Name of the file in the data directory caching the products discovered by
+// --refresh-products.
+const PRODUCTS_CACHE_FILENAME: &str = "products.json";
 
 /// A list of [HashiCorp](https://www.hashicorp.com/) products that this crate
 /// can download.
 pub const PRODUCTS_LIST: &[&str] = &[
+    "boundary",
     "consul",
     "nomad",
     "packer",
     "terraform",
     "vagrant",
     "vault",
+    "vault-radar",
+    "waypoint",
 ];
+
+/// The products in [`PRODUCTS_LIST`] that have enterprise (`+ent`) variants.
+pub const ENTERPRISE_PRODUCTS_LIST: &[&str] = &[
+    "consul",
+    "nomad",
+    "vault",
+];
+
+// Returns the path of the cached products list, if the shared data directory
+// can be found.
+fn cache_path() -> Option<PathBuf> {
+    let path = dirs::data_dir()?
+        .join(env!("CARGO_PKG_NAME"))
+        .join(PRODUCTS_CACHE_FILENAME);
+
+    Some(path)
+}
+
+/// Returns the known products, the static [`PRODUCTS_LIST`] merged with any
+/// products cached by [`store`]. A missing or unreadable cache is ignored,
+/// leaving only the static list.
+///
+/// This never touches the network, so it's cheap enough to call before the
+/// arguments are parsed.
+pub fn known() -> Vec<String> {
+    let cached = cache_path()
+        .and_then(|path| fs::read(path).ok())
+        .and_then(|content| serde_json::from_slice::<Vec<String>>(&content).ok())
+        .unwrap_or_default();
+
+    merge(&cached)
+}
+
+/// Stores the `discovered` products in the cache read by [`known`],
+/// returning the path of the cache. The cache is replaced atomically.
+///
+/// # Errors
+///
+/// Errors if the shared data directory can't be found, or the cache can't
+/// be written.
+pub fn store(discovered: &[String]) -> Result<PathBuf> {
+    let path = cache_path()
+        .context("couldn't find shared data directory")?;
+
+    // The path always has a parent, it was joined onto the data dir.
+    let dir = path.parent().unwrap();
+
+    fs::create_dir_all(dir)
+        .with_context(|| format!("couldn't create '{}'", dir.display()))?;
+
+    let mut products = discovered.to_vec();
+    products.sort();
+    products.dedup();
+
+    let content = serde_json::to_vec_pretty(&products)?;
+
+    let mut tmpfile = NamedTempFile::new_in(dir)
+        .with_context(|| format!("couldn't write '{}'", path.display()))?;
+
+    tmpfile.write_all(&content)
+        .with_context(|| format!("couldn't write '{}'", path.display()))?;
+
+    tmpfile.persist(&path)
+        .with_context(|| format!("couldn't write '{}'", path.display()))?;
+
+    Ok(path)
+}
+
+/// Merge the `discovered` products with the static [`PRODUCTS_LIST`],
+/// returning a sorted list without duplicates.
+pub fn merge(discovered: &[String]) -> Vec<String> {
+    let mut products: Vec<String> = PRODUCTS_LIST
+        .iter()
+        .map(ToString::to_string)
+        .chain(discovered.iter().cloned())
+        .collect();
+
+    products.sort();
+    products.dedup();
+
+    products
+}
+
+/// The outcome of matching a product name against the known products.
+pub enum ProductMatch<'a> {
+    /// The name is exactly a known product.
+    Exact(&'a str),
+
+    /// The name uniquely matches a known product by prefix or fuzzy match.
+    Assumed(&'a str),
+
+    /// The name matches several known products.
+    Ambiguous(Vec<&'a str>),
+
+    /// The name doesn't match any known product.
+    NoMatch,
+}
+
+/// Match `name` against the `known` products, see [`known`].
+///
+/// Exact matches are checked first, followed by prefix matches, e.g. `terra`
+/// for `terraform`, and finally fuzzy matches where the characters of `name`
+/// appear in order in the product name, starting with the same character,
+/// e.g. `tf` for `terraform`. Names are matched case insensitively.
+pub fn find<'a>(name: &str, known: &'a [String]) -> ProductMatch<'a> {
+    let name = name.to_lowercase();
+
+    if let Some(product) = known.iter().find(|product| **product == name) {
+        return ProductMatch::Exact(product);
+    }
+
+    let prefixed: Vec<&'a str> = known
+        .iter()
+        .map(String::as_str)
+        .filter(|product| product.starts_with(&name))
+        .collect();
+
+    let candidates = if prefixed.is_empty() {
+        known
+            .iter()
+            .map(String::as_str)
+            .filter(|product| is_fuzzy_match(&name, product))
+            .collect()
+    }
+    else {
+        prefixed
+    };
+
+    match candidates.as_slice() {
+        []        => ProductMatch::NoMatch,
+        [product] => ProductMatch::Assumed(product),
+        _         => ProductMatch::Ambiguous(candidates),
+    }
+}
+
+// Returns `true` if the characters of `name` appear in order in `product`,
+// with the first characters matching.
+fn is_fuzzy_match(name: &str, product: &str) -> bool {
+    let mut chars = product.chars();
+
+    match (name.chars().next(), chars.next()) {
+        (Some(a), Some(b)) if a == b => {},
+        _                            => return false,
+    }
+
+    name.chars().skip(1).all(|c| chars.any(|p| p == c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn known_products() -> Vec<String> {
+        merge(&[])
+    }
+
+    #[test]
+    fn test_merge() {
+        let discovered = vec![
+            "terraform".to_string(),
+            "consul-template".to_string(),
+        ];
+
+        let products = merge(&discovered);
+
+        assert!(products.contains(&"consul-template".to_string()));
+        assert_eq!(products.len(), PRODUCTS_LIST.len() + 1);
+        assert!(products.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_find_exact() {
+        let known = known_products();
+
+        assert!(matches!(find("vault", &known), ProductMatch::Exact("vault")));
+        assert!(matches!(find("Vault", &known), ProductMatch::Exact("vault")));
+    }
+
+    #[test]
+    fn test_find_prefix() {
+        let known = known_products();
+
+        assert!(matches!(find("terra", &known), ProductMatch::Assumed("terraform")));
+        assert!(matches!(find("Terra", &known), ProductMatch::Assumed("terraform")));
+    }
+
+    #[test]
+    fn test_find_ambiguous() {
+        let known = known_products();
+
+        let ProductMatch::Ambiguous(products) = find("vau", &known) else {
+            panic!("expected an ambiguous match");
+        };
+
+        assert_eq!(products, vec!["vault", "vault-radar"]);
+
+        let ProductMatch::Ambiguous(products) = find("vr", &known) else {
+            panic!("expected an ambiguous match");
+        };
+
+        assert_eq!(products, vec!["vagrant", "vault-radar"]);
+    }
+
+    #[test]
+    fn test_find_fuzzy() {
+        let known = known_products();
+
+        assert!(matches!(find("tf", &known), ProductMatch::Assumed("terraform")));
+        assert!(matches!(find("nmd", &known), ProductMatch::Assumed("nomad")));
+    }
+
+    #[test]
+    fn test_find_no_match() {
+        let known = known_products();
+
+        assert!(matches!(find("xyz", &known), ProductMatch::NoMatch));
+        assert!(matches!(find("ft", &known), ProductMatch::NoMatch));
+    }
+}
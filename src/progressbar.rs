@@ -1,12 +1,19 @@
 // client: HTTP client and associated methods
 #![forbid(unsafe_code)]
 #![forbid(missing_docs)]
+use console::Term;
 use indicatif::{
+    MultiProgress,
     ProgressBarIter,
     ProgressDrawTarget,
     ProgressStyle,
 };
-use std::io::Write;
+use std::io::{
+    self,
+    IsTerminal,
+    Write,
+};
+use std::sync::Arc;
 
 // How many times per second to redraw the progress bar.
 const PROGRESS_UPDATE_HZ: u8 = 8;
@@ -18,11 +25,29 @@ const PROGRESS_TEMPLATE: &str = concat!(
     "[{elapsed_precise}] ",
     "[{bar:40.cyan/blue}] ",
     "{bytes}/{total_bytes} ",
-    "({eta})",
+    "({bytes_per_sec}, {eta})",
     " {msg}",
 );
 
 const PROGRESS_TEMPLATE_NO_COLOR: &str = concat!(
+    "{spinner} ",
+    "[{elapsed_precise}] ",
+    "[{bar:40}] ",
+    "{bytes}/{total_bytes} ",
+    "({bytes_per_sec}, {eta})",
+    " {msg}",
+);
+
+const PROGRESS_TEMPLATE_NO_SPEED: &str = concat!(
+    "{spinner:.green} ",
+    "[{elapsed_precise}] ",
+    "[{bar:40.cyan/blue}] ",
+    "{bytes}/{total_bytes} ",
+    "({eta})",
+    " {msg}",
+);
+
+const PROGRESS_TEMPLATE_NO_COLOR_NO_SPEED: &str = concat!(
     "{spinner} ",
     "[{elapsed_precise}] ",
     "[{bar:40}] ",
@@ -32,11 +57,26 @@ const PROGRESS_TEMPLATE_NO_COLOR: &str = concat!(
 );
 
 /// A builder for [`ProgressBar`].
-#[derive(Default)]
 pub struct ProgressBarBuilder {
-    no_color: bool,
-    quiet:    bool,
-    size:     Option<u64>,
+    force:      bool,
+    multi:      Option<Arc<MultiProgress>>,
+    no_color:   bool,
+    quiet:      bool,
+    show_speed: bool,
+    size:       Option<u64>,
+}
+
+impl Default for ProgressBarBuilder {
+    fn default() -> Self {
+        Self {
+            force:      false,
+            multi:      None,
+            no_color:   false,
+            quiet:      false,
+            show_speed: true,
+            size:       None,
+        }
+    }
 }
 
 impl ProgressBarBuilder {
@@ -46,6 +86,22 @@ impl ProgressBarBuilder {
         Self::default()
     }
 
+    /// Draw the [`ProgressBar`] even when stderr isn't a terminal. By
+    /// default the bar is hidden, to keep escape codes out of logs.
+    #[must_use]
+    pub fn force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Register the [`ProgressBar`] with the given [`MultiProgress`], so that
+    /// several bars can be drawn at once without clobbering each other.
+    #[must_use]
+    pub fn multi(mut self, multi: Arc<MultiProgress>) -> Self {
+        self.multi = Some(multi);
+        self
+    }
+
     /// Disable the [`ProgressBar`] colours.
     #[must_use]
     pub fn no_color(mut self, no_color: bool) -> Self {
@@ -60,6 +116,14 @@ impl ProgressBarBuilder {
         self
     }
 
+    /// Show the transfer speed on the [`ProgressBar`]. This is on by
+    /// default.
+    #[must_use]
+    pub fn show_speed(mut self, show_speed: bool) -> Self {
+        self.show_speed = show_speed;
+        self
+    }
+
     /// Set the size of the [`ProgressBar`].
     #[must_use]
     pub fn size(mut self, size: Option<u64>) -> Self {
@@ -71,25 +135,21 @@ impl ProgressBarBuilder {
     #[allow(clippy::missing_panics_doc)]
     #[must_use]
     pub fn build(self) -> ProgressBar {
-        // No progress bar for quiet mode.
-        if self.quiet {
+        // No progress bar for quiet mode, or when stderr isn't a terminal
+        // unless we're forced to draw one.
+        let is_terminal = io::stderr().is_terminal();
+
+        if self.quiet || !(is_terminal || self.force) {
             return ProgressBar {
                 bar: indicatif::ProgressBar::hidden(),
             };
         }
 
-        // We want to limit refreshes to once per second, so we have to make a
-        // new draw target.
-        let target = ProgressDrawTarget::stderr_with_hz(PROGRESS_UPDATE_HZ);
+        let target = draw_target(is_terminal);
 
         let bar = if self.size.is_some() {
             // If we know the total size, setup a nice bar
-            let template = if self.no_color {
-                PROGRESS_TEMPLATE_NO_COLOR
-            }
-            else {
-                PROGRESS_TEMPLATE
-            };
+            let template = template(self.no_color, self.show_speed);
 
             // We shouldn't ever panic here, since our progress bar templates
             // are not user provided, we've tested them.
@@ -115,12 +175,52 @@ impl ProgressBarBuilder {
             pb
         };
 
+        // When drawing multiple bars, the MultiProgress takes over drawing
+        // the bar.
+        let bar = match self.multi {
+            Some(multi) => multi.add(bar),
+            None        => bar,
+        };
+
         ProgressBar {
             bar,
         }
     }
 }
 
+/// Create a [`MultiProgress`] that several [`ProgressBar`]s can be registered
+/// with, see [`ProgressBarBuilder::multi`]. It draws wherever a single bar
+/// would.
+#[must_use]
+pub fn multi_progress() -> MultiProgress {
+    MultiProgress::with_draw_target(draw_target(io::stderr().is_terminal()))
+}
+
+// We want to limit refreshes to once per second, so we have to make a new
+// draw target. indicatif never draws to a stderr that isn't a terminal, so a
+// forced bar draws to the terminal handle directly.
+fn draw_target(is_terminal: bool) -> ProgressDrawTarget {
+    if is_terminal {
+        ProgressDrawTarget::stderr_with_hz(PROGRESS_UPDATE_HZ)
+    }
+    else {
+        ProgressDrawTarget::term_like_with_hz(
+            Box::new(Term::stderr()),
+            PROGRESS_UPDATE_HZ,
+        )
+    }
+}
+
+// Select the progress bar template for the colour and speed settings.
+fn template(no_color: bool, show_speed: bool) -> &'static str {
+    match (no_color, show_speed) {
+        (false, true)  => PROGRESS_TEMPLATE,
+        (true, true)   => PROGRESS_TEMPLATE_NO_COLOR,
+        (false, false) => PROGRESS_TEMPLATE_NO_SPEED,
+        (true, false)  => PROGRESS_TEMPLATE_NO_COLOR_NO_SPEED,
+    }
+}
+
 /// A wrapper for an [`indicatif::ProgressBar`].
 #[derive(Debug)]
 pub struct ProgressBar {
@@ -137,4 +237,40 @@ impl ProgressBar {
     pub fn finished(&self) {
         self.bar.finish_with_message(PROGRESS_FINISHED_MSG);
     }
+
+    /// Sets the [`ProgressBar`] position, used when a download is resumed.
+    pub fn set_position(&self, position: u64) {
+        self.bar.set_position(position);
+    }
+
+    /// Resets the [`ProgressBar`] position, used when a download restarts.
+    pub fn reset(&self) {
+        self.bar.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_templates() {
+        for (no_color, show_speed) in [
+            (false, true),
+            (true, true),
+            (false, false),
+            (true, false),
+        ] {
+            let template = template(no_color, show_speed);
+
+            assert!(ProgressStyle::default_bar().template(template).is_ok());
+            assert_eq!(template.contains("{bytes_per_sec}"), show_speed);
+        }
+    }
+
+    #[test]
+    fn test_show_speed_default() {
+        assert!(ProgressBarBuilder::new().show_speed);
+        assert!(!ProgressBarBuilder::new().show_speed(false).show_speed);
+    }
 }
@@ -1,6 +1,7 @@
 // shasums: Handle checking of files against shasums
 #![forbid(unsafe_code)]
 #![forbid(missing_docs)]
+use crate::client::build::Build;
 use crate::tmpfile::TmpFile;
 use super::error::ShasumsError;
 use sha2::{
@@ -8,16 +9,31 @@ use sha2::{
     Sha256,
 };
 use std::collections::HashMap;
-use std::io;
+use std::fs::File;
+use std::io::{
+    self,
+    Read,
+    Write,
+};
+use std::path::Path;
 
 /// This enum represents the outcome of shasum verification.
 #[derive(Debug, Eq, PartialEq)]
 pub enum Checksum {
     /// Sha checksum did not verify.
-    Bad,
+    Mismatch {
+        /// The expected hex encoded SHA256 from the shasums content.
+        expected: String,
+
+        /// The actual hex encoded SHA256 of the file.
+        actual: String,
+    },
+
+    /// The file to verify was missing.
+    Missing,
 
     /// Sha checksum verified correctly.
-    OK,
+    Ok,
 }
 
 /// [`Shasums`] represents a downloaded shasum file.
@@ -37,23 +53,33 @@ impl Shasums {
 
     // Parses the shasums text into an easy to use hash.
     // Expects to find a whitespace separated file with two columns of
-    // "shasum filename".
-    // Panics if the shasums content can't be parsed.
-    fn parse(&self) -> HashMap<&str, &str> {
+    // "shasum filename". Filenames may be prefixed with a `*` binary mode
+    // marker, as output by GNU coreutils. Blank lines are skipped.
+    // Errors if the shasums content can't be parsed.
+    fn parse(&self) -> Result<HashMap<&str, &str>, ShasumsError> {
         let mut hash = HashMap::new();
 
         for line in self.content().lines() {
             let split: Vec<&str> = line.split_whitespace().collect();
 
-            assert!(split.len() == 2, "malformed shasums file");
+            if split.is_empty() {
+                continue;
+            }
+
+            if split.len() != 2 {
+                return Err(ShasumsError::Malformed(line.to_string()));
+            }
 
             let shasum   = split[0];
             let filename = split[1];
 
+            // Strip the binary mode marker, if present.
+            let filename = filename.strip_prefix('*').unwrap_or(filename);
+
             hash.insert(filename, shasum);
         }
 
-        hash
+        Ok(hash)
     }
 
     /// Check the shasum of the given `tmpfile` against our [`Shasums`]
@@ -62,6 +88,7 @@ impl Shasums {
     /// # Errors
     ///
     /// Can error if:
+    ///   - Failing to parse the shasums content
     ///   - Failing to find the shasum for the `tmpfile` filename
     ///   - Failing to obtain a handle for the `tmpfile`
     ///   - Failing to hash the file content
@@ -69,25 +96,138 @@ impl Shasums {
         &self,
         tmpfile: &mut TmpFile,
     ) -> Result<Checksum, ShasumsError> {
-        let filename = tmpfile.filename();
-        let shasum   = self.shasum(filename)
+        let filename = tmpfile.filename().to_string();
+
+        // Use the digest calculated while the tmpfile was written if there is
+        // one, saving a second pass over the file.
+        if let Some(actual) = tmpfile.sha256() {
+            let actual = actual.to_string();
+
+            return self.compare(&filename, actual);
+        }
+
+        let file = tmpfile.handle()?;
+
+        self.check_reader(&filename, file)
+    }
+
+    /// Check the shasum of the in memory `content` of `filename` against our
+    /// [`Shasums`] content.
+    ///
+    /// # Errors
+    ///
+    /// Can error if:
+    ///   - Failing to parse the shasums content
+    ///   - Failing to find the shasum for the `filename`
+    pub fn check_bytes(
+        &self,
+        filename: &str,
+        content: &[u8],
+    ) -> Result<Checksum, ShasumsError> {
+        self.check_reader(filename, content)
+    }
+
+    /// Check the shasum of the file at `path` against our [`Shasums`]
+    /// content, looking the shasum up by the basename of the `path`.
+    ///
+    /// # Errors
+    ///
+    /// Can error if:
+    ///   - Failing to parse the shasums content
+    ///   - Failing to find the shasum for the `path` basename
+    ///   - Failing to open or hash the file
+    pub fn check_file(&self, path: &Path) -> Result<Checksum, ShasumsError> {
+        let filename = path
+            .file_name()
+            .and_then(|name| name.to_str())
             .ok_or_else(|| {
-                ShasumsError::NoShasumForFile(filename.to_string())
+                ShasumsError::NoShasumForFile(path.display().to_string())
             })?;
 
-        let mut file   = tmpfile.handle()?;
+        let file = File::open(path)
+            .map_err(|_err| ShasumsError::Hashing)?;
+
+        self.check_reader(filename, file)
+    }
+
+    /// Check every file in our [`Shasums`] content that exists in `dir`,
+    /// returning the [`Checksum`] for each filename, sorted by filename.
+    /// Files that don't exist in `dir` are reported as [`Checksum::Missing`].
+    ///
+    /// # Errors
+    ///
+    /// Can error if:
+    ///   - Failing to parse the shasums content
+    ///   - Failing to open or hash any of the files
+    pub fn verify_all(
+        &self,
+        dir: &Path,
+    ) -> Result<Vec<(String, Checksum)>, ShasumsError> {
+        let mut results = Vec::new();
+
+        for filename in self.filenames()? {
+            let path = dir.join(filename);
+
+            // Filenames with path components could point outside of `dir`,
+            // so they're never looked for.
+            let is_plain = Path::new(filename).file_name()
+                .is_some_and(|name| name == filename);
+
+            let checksum = if is_plain && path.is_file() {
+                self.check_file(&path)?
+            }
+            else {
+                Checksum::Missing
+            };
+
+            results.push((filename.to_string(), checksum));
+        }
+
+        Ok(results)
+    }
+
+    // Hash the content of `reader` and check it against the shasum for
+    // `filename`.
+    fn check_reader<R: Read>(
+        &self,
+        filename: &str,
+        mut reader: R,
+    ) -> Result<Checksum, ShasumsError> {
+        // Fail on a missing shasum before spending time hashing.
+        if self.shasum(filename)?.is_none() {
+            return Err(ShasumsError::NoShasumForFile(filename.to_string()));
+        }
+
         let mut hasher = Sha256::new();
 
-        io::copy(&mut file, &mut hasher)
+        io::copy(&mut reader, &mut hasher)
             .map_err(|_err| ShasumsError::Hashing)?;
 
-        let hash = hasher.finalize();
+        let hash = hex::encode(hasher.finalize());
+
+        self.compare(filename, hash)
+    }
+
+    // Compare the hex encoded SHA256 `hash` against the shasum for
+    // `filename`.
+    fn compare(
+        &self,
+        filename: &str,
+        hash: String,
+    ) -> Result<Checksum, ShasumsError> {
+        let shasum = self.shasum(filename)?
+            .ok_or_else(|| {
+                ShasumsError::NoShasumForFile(filename.to_string())
+            })?;
 
-        let res = if hex::encode(hash) == shasum {
-            Checksum::OK
+        let res = if hash == shasum {
+            Checksum::Ok
         }
         else {
-            Checksum::Bad
+            Checksum::Mismatch {
+                expected: shasum.to_string(),
+                actual:   hash,
+            }
         };
 
         Ok(res)
@@ -99,12 +239,117 @@ impl Shasums {
         &self.content
     }
 
-    // Return the shasum for the specified filename
-    fn shasum(&self, filename: &str) -> Option<&str> {
-        let parsed = self.parse();
-        let shasum = parsed.get(filename)?;
+    /// Return a map of every filename in the [`Shasums`] to its shasum.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the shasums content can't be parsed.
+    pub fn digests(&self) -> Result<HashMap<String, String>, ShasumsError> {
+        let digests = self.parse()?
+            .into_iter()
+            .map(|(filename, shasum)| (filename.to_string(), shasum.to_string()))
+            .collect();
+
+        Ok(digests)
+    }
+
+    /// Return the shasum for the given [`Build`], looked up by the basename
+    /// of its URL, if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the shasums content can't be parsed.
+    pub fn for_build(&self, build: &Build) -> Result<Option<&str>, ShasumsError> {
+        let filename = build.url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .unwrap_or_default();
+
+        self.shasum(filename)
+    }
+
+    /// Return every filename covered by the [`Shasums`], sorted by filename.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the shasums content can't be parsed.
+    pub fn filenames(&self) -> Result<Vec<&str>, ShasumsError> {
+        let mut filenames: Vec<&str> = self.parse()?.into_keys().collect();
+        filenames.sort_unstable();
+
+        Ok(filenames)
+    }
+
+    /// Return the shasum for the specified `filename`, if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the shasums content can't be parsed.
+    pub fn shasum(&self, filename: &str) -> Result<Option<&str>, ShasumsError> {
+        let parsed = self.parse()?;
+        let shasum = parsed.get(filename).copied();
+
+        Ok(shasum)
+    }
+}
+
+/// A [`Write`] wrapper that calculates the SHA256 of everything written
+/// through it, so content can be checked without reading it back.
+#[derive(Debug)]
+pub struct Sha256Writer<W> {
+    hasher: Sha256,
+    inner:  W,
+}
+
+impl<W: Write> Sha256Writer<W> {
+    /// Create a new [`Sha256Writer`] wrapping `inner`.
+    pub fn new(inner: W) -> Self {
+        Self::with_hasher(inner, Sha256::new())
+    }
 
-        Some(shasum)
+    /// Create a new [`Sha256Writer`] wrapping `inner`, continuing from a
+    /// `hasher` that has already seen some content.
+    pub fn with_hasher(inner: W, hasher: Sha256) -> Self {
+        Self {
+            hasher: hasher,
+            inner:  inner,
+        }
+    }
+
+    /// Return the hex encoded SHA256 of everything written so far.
+    #[must_use]
+    pub fn hex_digest(&self) -> String {
+        hex::encode(self.hasher.clone().finalize())
+    }
+
+    /// Return a mutable reference to the inner writer. Content written
+    /// directly to the inner writer isn't hashed.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Unwrap this [`Sha256Writer`], returning the inner writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Forget everything written so far, for when the inner writer is
+    /// truncated.
+    pub fn reset(&mut self) {
+        self.hasher = Sha256::new();
+    }
+}
+
+impl<W: Write> Write for Sha256Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
     }
 }
 
@@ -130,6 +375,37 @@ mod tests {
         tmpfile
     }
 
+    #[test]
+    fn test_sha256_writer() {
+        let mut writer = Sha256Writer::new(Vec::new());
+
+        writer.write_all(b"Changed text\n").unwrap();
+
+        assert_eq!(
+            writer.hex_digest(),
+            "f94f850433bd342a63941ad04a36adfdbbc47d2637cd9fca467fb21a92c4ec5e",
+        );
+        assert_eq!(writer.get_mut(), b"Changed text\n");
+
+        writer.reset();
+
+        assert_eq!(
+            writer.hex_digest(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        );
+    }
+
+    #[test]
+    fn test_check_inline_digest() {
+        let shasums = Shasums::new("abc123  test.zip\n".into());
+
+        let mut tmpfile = TmpFile::new("test.zip").unwrap();
+        tmpfile.set_sha256("abc123".into());
+
+        // The content isn't hashed when there's already a digest.
+        assert_eq!(shasums.check(&mut tmpfile).unwrap(), Checksum::Ok);
+    }
+
     #[test]
     fn test_check_bad_checksum() {
         let test_data_path = concat!(
@@ -149,7 +425,12 @@ mod tests {
         let shasums = Shasums::new(shasums_content.into());
         let res     = shasums.check(&mut tmpfile).unwrap();
 
-        assert_eq!(Checksum::Bad, res);
+        let expected = Checksum::Mismatch {
+            expected: "bad".into(),
+            actual:   "bd6abe380b9ffdca9375f1202b36e1c7b8ca3e8b5de4ae8582c0037949c30ce8".into(),
+        };
+
+        assert_eq!(expected, res);
     }
 
     #[test]
@@ -196,7 +477,7 @@ mod tests {
         let shasums = Shasums::new(shasums_content.into());
         let res     = shasums.check(&mut tmpfile).unwrap();
 
-        assert_eq!(Checksum::OK, res);
+        assert_eq!(Checksum::Ok, res);
     }
 
     #[test]
@@ -221,7 +502,27 @@ mod tests {
         );
 
         let shasums = Shasums::new(shasums_content.into());
-        let parsed  = shasums.parse();
+        let parsed  = shasums.parse().unwrap();
+
+        let mut expected = HashMap::new();
+        expected.insert(
+            "test",
+            "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03",
+        );
+
+        assert_eq!(expected, parsed)
+    }
+
+    #[test]
+    fn test_parse_binary_marker() {
+        let shasums_content = format!(
+            "{shasum} *{filename}",
+            shasum = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03",
+            filename = "test",
+        );
+
+        let shasums = Shasums::new(shasums_content.into());
+        let parsed  = shasums.parse().unwrap();
 
         let mut expected = HashMap::new();
         expected.insert(
@@ -232,30 +533,91 @@ mod tests {
         assert_eq!(expected, parsed)
     }
 
+    #[test]
+    fn test_digests() {
+        let shasums = Shasums::new(
+            "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03  test\n".into(),
+        );
+
+        let mut expected = HashMap::new();
+        expected.insert(
+            "test".to_string(),
+            "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03".to_string(),
+        );
+
+        assert_eq!(expected, shasums.digests().unwrap())
+    }
+
+    #[test]
+    fn test_for_build() {
+        let shasums = Shasums::new(
+            "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03  terraform_0.15.1_linux_amd64.zip\n".into(),
+        );
+
+        let build = |filename: &str| {
+            Build {
+                arch: "amd64".into(),
+                os:   "linux".into(),
+                url:  format!("https://releases.hashicorp.com/terraform/0.15.1/{filename}")
+                    .parse()
+                    .unwrap(),
+            }
+        };
+
+        assert_eq!(
+            shasums.for_build(&build("terraform_0.15.1_linux_amd64.zip")).unwrap(),
+            Some("5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03"),
+        );
+        assert_eq!(
+            shasums.for_build(&build("terraform_0.15.1_linux_arm64.zip")).unwrap(),
+            None,
+        );
+    }
+
+    #[test]
+    fn test_filenames() {
+        let shasums = Shasums::new(
+            "\n\
+            bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb  vault_1.0.0_linux_amd64.zip\n\
+            \n\
+            aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa *vault_1.0.0_darwin_arm64.zip  \n\
+            \t\n".into(),
+        );
+
+        let expected = vec![
+            "vault_1.0.0_darwin_arm64.zip",
+            "vault_1.0.0_linux_amd64.zip",
+        ];
+
+        assert_eq!(expected, shasums.filenames().unwrap())
+    }
+
     #[test]
     fn test_parse_empty_content() {
         let shasums = Shasums::new("".into());
-        let parsed  = shasums.parse();
+        let parsed  = shasums.parse().unwrap();
 
         assert!(parsed.len() == 0)
     }
 
     #[test]
-    #[should_panic(expected = "malformed shasums file")]
-    fn test_parse_panic_single_column() {
+    fn test_parse_err_single_column() {
         let shasums_content = format!(
             "{shasum}",
             shasum = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03",
         );
 
         let shasums = Shasums::new(shasums_content.into());
+        let res     = shasums.parse();
 
-        shasums.parse();
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "malformed shasums line: '5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03'",
+        );
     }
 
     #[test]
-    #[should_panic(expected = "malformed shasums file")]
-    fn test_parse_panic_extra_column() {
+    fn test_parse_err_extra_column() {
         let shasums_content = format!(
             "{shasum} {filename} {extra}",
             shasum = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03",
@@ -264,8 +626,9 @@ mod tests {
         );
 
         let shasums = Shasums::new(shasums_content.into());
+        let res     = shasums.parse();
 
-        shasums.parse();
+        assert!(matches!(res.unwrap_err(), ShasumsError::Malformed(_)));
     }
 
     #[test]
@@ -278,8 +641,82 @@ mod tests {
 
         let shasums  = Shasums::new(shasums_content.into());
         let expected = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
-        let ret      = shasums.shasum("test").unwrap();
+        let ret      = shasums.shasum("test").unwrap().unwrap();
 
         assert_eq!(expected, ret)
     }
+
+    #[test]
+    fn test_check_bytes() {
+        let shasums = Shasums::new(
+            "f94f850433bd342a63941ad04a36adfdbbc47d2637cd9fca467fb21a92c4ec5e test.txt".into(),
+        );
+
+        let res = shasums.check_bytes("test.txt", b"Changed text\n").unwrap();
+        assert_eq!(Checksum::Ok, res);
+
+        let res = shasums.check_bytes("test.txt", b"Other text\n").unwrap();
+        assert!(matches!(res, Checksum::Mismatch { .. }));
+    }
+
+    #[test]
+    fn test_check_file_ok() {
+        let path = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test-data/shasums-check.txt",
+        ));
+
+        let shasums = Shasums::new(
+            "bd6abe380b9ffdca9375f1202b36e1c7b8ca3e8b5de4ae8582c0037949c30ce8 shasums-check.txt".into(),
+        );
+
+        let res = shasums.check_file(path).unwrap();
+
+        assert_eq!(Checksum::Ok, res);
+    }
+
+    #[test]
+    fn test_check_file_bad() {
+        let path = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test-data/shasums-check.txt",
+        ));
+
+        let shasums = Shasums::new("bad shasums-check.txt".into());
+        let res     = shasums.check_file(path).unwrap();
+
+        let expected = Checksum::Mismatch {
+            expected: "bad".into(),
+            actual:   "bd6abe380b9ffdca9375f1202b36e1c7b8ca3e8b5de4ae8582c0037949c30ce8".into(),
+        };
+
+        assert_eq!(expected, res);
+    }
+
+    #[test]
+    fn test_verify_all() {
+        let dir = tempfile::tempdir().unwrap();
+
+        std::fs::write(dir.path().join("good.txt"), b"Test text\n").unwrap();
+        std::fs::write(dir.path().join("bad.txt"), b"Changed text\n").unwrap();
+        std::fs::write(dir.path().join("unlisted.txt"), b"Test text\n").unwrap();
+
+        let sha = "66d5b2cc06203137f8a0e9714638dc1085c57a3f1fa26c8823ae5cf89ab26488";
+
+        let shasums = Shasums::new(format!(
+            "{sha}  good.txt\n{sha}  bad.txt\n{sha}  missing.txt\n{sha}  ../good.txt\n",
+        ));
+
+        let res = shasums.verify_all(dir.path()).unwrap();
+
+        assert_eq!(res, vec![
+            ("../good.txt".to_string(), Checksum::Missing),
+            ("bad.txt".to_string(), Checksum::Mismatch {
+                expected: sha.to_string(),
+                actual:   "f94f850433bd342a63941ad04a36adfdbbc47d2637cd9fca467fb21a92c4ec5e".to_string(),
+            }),
+            ("good.txt".to_string(), Checksum::Ok),
+            ("missing.txt".to_string(), Checksum::Missing),
+        ]);
+    }
 }
@@ -3,45 +3,134 @@
 #![forbid(missing_docs)]
 use super::error::SignatureError;
 use super::shasums::Shasums;
-use bytes::{
-    Buf,
-    Bytes,
+use bytes::Bytes;
+use chrono::{
+    DateTime,
+    Utc,
 };
 use pgp::composed::{
     Deserializable,
     StandaloneSignature,
 };
 use pgp::composed::signed_key::SignedPublicKey;
-use std::io::BufReader;
-use std::io::Cursor;
+use pgp::types::PublicKeyTrait;
+use std::fs;
+use std::io::{
+    Cursor,
+    Write,
+};
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::time::Duration;
 
-#[cfg(any(test, not(feature = "embed_gpg_key")))]
-use std::io::prelude::*;
+const HASHICORP_GPG_KEY_FILENAME: &str = "hashicorp.asc";
 
-#[cfg(any(test, not(feature = "embed_gpg_key")))]
-use std::fs::File;
+// Suffix of the backup of a replaced GPG key, e.g. `hashicorp.asc.bak`.
+const BACKUP_SUFFIX: &str = ".bak";
 
-#[cfg(any(test, not(feature = "embed_gpg_key")))]
-use std::path::PathBuf;
+// Armored keys and signatures start with this, binary ones never do.
+const ARMOR_HEADER: &[u8] = b"-----BEGIN";
 
-#[cfg(any(test, not(feature = "embed_gpg_key")))]
-const HASHICORP_GPG_KEY_FILENAME: &str = "hashicorp.asc";
+/// The fingerprint of the HashiCorp GPG key, as published at
+/// <https://www.hashicorp.com/security>. The embedded or stored key must
+/// match this before it is used.
+pub const HASHICORP_GPG_KEY_FINGERPRINT: &str = "C874011F0AB405110D02105534365D9472D7468F";
 
 #[cfg(feature = "embed_gpg_key")]
 const HASHICORP_GPG_KEY: &str = include_str!("../gpg/hashicorp.asc");
 
+/// The default for how far in the future a signature may appear to have been
+/// created before [`VerificationReport::clock_skew`] reports it.
+pub const DEFAULT_MAX_CLOCK_SKEW: Duration = Duration::from_secs(24 * 60 * 60);
+
+// Signatures appearing to be older than this are implausible, HashiCorp
+// haven't been signing releases for anywhere near this long.
+const MAX_SIGNATURE_AGE: Duration = Duration::from_secs(20 * 365 * 24 * 60 * 60);
+
+/// A sign that the local clock is wrong, from comparing it against when a
+/// signature was created.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ClockSkew {
+    /// The signature appears to have been created this far in the future,
+    /// the local clock is probably behind.
+    Future(Duration),
+
+    /// The signature appears to be implausibly old, having been created this
+    /// long ago, the local clock is probably ahead.
+    Old(Duration),
+}
+
+/// The result of successfully checking a [`Signature`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerificationReport {
+    /// When the signature was created, if recorded in the signature.
+    pub created: Option<DateTime<Utc>>,
+
+    /// The fingerprint of the key that verified the signature, as an
+    /// uppercase hex string.
+    pub fingerprint: String,
+
+    /// The ID of the key that verified the signature, as an uppercase hex
+    /// string.
+    pub key_id: String,
+}
+
+impl VerificationReport {
+    /// Returns the short, 8 character, form of the `key_id`.
+    #[must_use]
+    pub fn short_key_id(&self) -> &str {
+        let start = self.key_id.len().saturating_sub(8);
+
+        &self.key_id[start..]
+    }
+
+    /// Compares when the signature was created against `now`, returning a
+    /// [`ClockSkew`] if it appears to have been created more than
+    /// `max_skew` in the future, or is implausibly old. Neither stops the
+    /// signature verifying, but both usually mean that the local clock is
+    /// wrong, as is common in containers.
+    ///
+    /// Returns `None` if the signature has no creation time.
+    #[must_use]
+    pub fn clock_skew(
+        &self,
+        now: DateTime<Utc>,
+        max_skew: Duration,
+    ) -> Option<ClockSkew> {
+        let created = self.created?;
+
+        // Only one of these can be positive.
+        let ahead = (created - now).to_std().ok();
+        let age   = (now - created).to_std().ok();
+
+        match (ahead, age) {
+            (Some(ahead), _) if ahead > max_skew => Some(ClockSkew::Future(ahead)),
+            (_, Some(age)) if age > MAX_SIGNATURE_AGE => Some(ClockSkew::Old(age)),
+            _ => None,
+        }
+    }
+}
+
 /// Handle checking `signature` against `public_key`.
+///
+/// [`Signature`]s are equal if they have the same signature and public key.
 #[derive(Debug)]
 pub struct Signature {
     // The public key
     public_key: SignedPublicKey,
 
+    // If set, only the key with this normalised ID may have made the
+    // signature.
+    required_key_id: Option<String>,
+
     // This is the signature of the shasums file.
     signature: StandaloneSignature,
 }
 
-// Only used by client::test_get_signature.
-#[cfg(test)]
+// Signatures are equal if they're the same signature checked against the
+// same public key, any required key ID isn't compared.
 impl PartialEq for Signature {
     fn eq(&self, other: &Self) -> bool {
         let public_key_match = self.public_key == other.public_key;
@@ -51,6 +140,8 @@ impl PartialEq for Signature {
     }
 }
 
+impl Eq for Signature {}
+
 impl Signature {
     /// Create a new [`Signature`] handler from the given `signature`.
     ///
@@ -58,7 +149,7 @@ impl Signature {
     ///
     /// Can error if failing to get the public key.
     pub fn new(signature: Bytes) -> Result<Self, SignatureError> {
-        let public_key = get_public_key()?;
+        let public_key = get_public_key(None)?;
 
         let signature = Self::with_public_key(
             signature,
@@ -68,65 +159,192 @@ impl Signature {
         Ok(signature)
     }
 
+    /// Create a new [`Signature`] handler from the given `signature` and the
+    /// armored or binary public key in the file at `path`. This takes priority over
+    /// the embedded or data directory key.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SignatureError::GpgKey`] if the file at `path` is missing or
+    /// can't be parsed, and can error if failing to read the signature.
+    pub fn with_public_key_path(
+        signature: Bytes,
+        path: &Path,
+    ) -> Result<Self, SignatureError> {
+        let public_key = get_public_key(Some(path))?;
+
+        Self::with_public_key(signature, &public_key)
+    }
+
     /// Create a new [`Signature`] handler from the given `signature` and
-    /// `public_key`.
+    /// `public_key`. Both may be either armored or binary.
     ///
     /// # Errors
     ///
-    /// Can error if failing to read the public key or the signature.
+    /// Returns [`SignatureError::PublicKey`] or
+    /// [`SignatureError::Signature`] if failing to parse the public key or
+    /// the signature.
     pub fn with_public_key(
         signature: Bytes,
-        public_key: &str,
+        public_key: impl AsRef<[u8]>,
     ) -> Result<Self, SignatureError> {
-        let mut cursor = Cursor::new(public_key.as_bytes());
-        let public_key = SignedPublicKey::from_armor_single(&mut cursor)?;
-        let reader = BufReader::new(signature.reader());
-        let signature = StandaloneSignature::from_bytes(reader)?;
+        let public_key = parse_public_key(public_key.as_ref())?;
+        let signature  = parse_signature(&signature)?;
 
         let signature = Self {
-            signature:  signature,
-            public_key: public_key.0,
+            signature:       signature,
+            public_key:      public_key,
+            required_key_id: None,
         };
 
         Ok(signature)
     }
 
-    /// Check the given [`Shasums`] content against the [`Signature`].
+    /// Require that the [`Signature`] was made by the key with the given
+    /// `key_id`, rejecting signatures made by any other key, even if it's
+    /// part of the trusted public key. The `key_id` is parsed as by
+    /// [`parse_key_id`].
     ///
     /// # Errors
     ///
-    /// Will return an error if unable to verify the signature against the
-    /// public key or any of its subkeys.
-    pub fn check(&self, shasums: &Shasums) -> Result<(), SignatureError> {
-        let shasums = shasums.content().as_bytes();
+    /// Returns [`SignatureError::InvalidKeyId`] if the `key_id` isn't valid.
+    pub fn require_key_id(mut self, key_id: &str) -> Result<Self, SignatureError> {
+        self.required_key_id = Some(parse_key_id(key_id)?);
 
-        // We have to check the signature against all public subkeys and the
-        // overall public key.
+        Ok(self)
+    }
+
+    /// Check the given [`Shasums`] content against the [`Signature`],
+    /// returning a [`VerificationReport`] describing the key that verified
+    /// it.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`SignatureError::Verification`] if unable to verify the
+    /// signature against the public key or any of its subkeys, or
+    /// [`SignatureError::KeyMismatch`] if it was made by a key other than
+    /// the one required by [`Signature::require_key_id`].
+    pub fn check(
+        &self,
+        shasums: &Shasums,
+    ) -> Result<VerificationReport, SignatureError> {
+        self.check_bytes(shasums.content().as_bytes())
+    }
+
+    /// Check the given `data` against the [`Signature`], as a detached
+    /// signature over any file, returning a [`VerificationReport`] describing
+    /// the key that verified it.
+    ///
+    /// # Errors
+    ///
+    /// Errors under the same conditions as [`Signature::check`].
+    pub fn check_bytes(
+        &self,
+        data: &[u8],
+    ) -> Result<VerificationReport, SignatureError> {
+        let report = self.verify(data)?;
+
+        if let Some(expected) = &self.required_key_id {
+            // Short and long key IDs are suffixes of the fingerprint.
+            if !report.fingerprint.ends_with(expected.as_str()) {
+                return Err(SignatureError::KeyMismatch {
+                    expected: expected.clone(),
+                    actual:   report.key_id,
+                });
+            }
+        }
+
+        Ok(report)
+    }
+
+    // Verify the signature of `content` against all of the public subkeys
+    // and the overall public key.
+    fn verify(&self, content: &[u8]) -> Result<VerificationReport, SignatureError> {
         for subkey in &self.public_key.public_subkeys {
-            match self.signature.verify(&subkey, shasums) {
+            match self.signature.verify(&subkey, content) {
                 Err(_) => continue,
-                Ok(()) => return Ok(()),
+                Ok(()) => return Ok(self.report(subkey)),
             }
         }
 
         // One last attempt, check against the main public key.
-        self.signature.verify(&self.public_key, shasums)
+        self.signature.verify(&self.public_key, content)
             .map_err(|_err| SignatureError::Verification)?;
 
-        Ok(())
+        Ok(self.report(&self.public_key))
+    }
+
+    // Build the VerificationReport for a signature verified by `key`.
+    fn report(&self, key: &impl PublicKeyTrait) -> VerificationReport {
+        VerificationReport {
+            created:     self.signature.signature.created().copied(),
+            fingerprint: hex::encode_upper(key.fingerprint().as_bytes()),
+            key_id:      hex::encode_upper(key.key_id()),
+        }
+    }
+}
+
+/// Parses a GPG `key_id`, given as a short (8 character) or long (16
+/// character) key ID or a full (40 character) fingerprint, optionally
+/// prefixed with `0x`. Returns the key ID as an uppercase hex string.
+///
+/// # Errors
+///
+/// Returns [`SignatureError::InvalidKeyId`] if the `key_id` isn't hex, or
+/// isn't one of the expected lengths.
+pub fn parse_key_id(key_id: &str) -> Result<String, SignatureError> {
+    let hex = key_id.trim();
+    let hex = hex
+        .strip_prefix("0x")
+        .or_else(|| hex.strip_prefix("0X"))
+        .unwrap_or(hex);
+
+    let valid = matches!(hex.len(), 8 | 16 | 40)
+        && hex.chars().all(|c| c.is_ascii_hexdigit());
+
+    if !valid {
+        return Err(SignatureError::InvalidKeyId(key_id.to_string()));
+    }
+
+    Ok(hex.to_ascii_uppercase())
+}
+
+// Returns true if `input` is ASCII armored rather than binary.
+fn is_armored(input: &[u8]) -> bool {
+    let start = input
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(input.len());
+
+    input[start..].starts_with(ARMOR_HEADER)
+}
+
+// Parse an armored or binary `public_key`.
+fn parse_public_key(public_key: &[u8]) -> Result<SignedPublicKey, SignatureError> {
+    let cursor = Cursor::new(public_key);
+
+    let public_key = if is_armored(public_key) {
+        SignedPublicKey::from_armor_single(cursor).map(|(key, _headers)| key)
     }
+    else {
+        SignedPublicKey::from_bytes(cursor)
+    };
+
+    public_key.map_err(SignatureError::PublicKey)
 }
 
-// Read a file's content into a String
-#[cfg(any(test, not(feature = "embed_gpg_key")))]
-fn read_file_content(path: &PathBuf) -> Result<String, SignatureError> {
-    let file         = File::open(path)?;
-    let mut reader   = BufReader::new(file);
-    let mut contents = String::new();
+// Parse an armored or binary `signature`.
+fn parse_signature(signature: &[u8]) -> Result<StandaloneSignature, SignatureError> {
+    let cursor = Cursor::new(signature);
 
-    reader.read_to_string(&mut contents)?;
+    let signature = if is_armored(signature) {
+        StandaloneSignature::from_armor_single(cursor).map(|(sig, _headers)| sig)
+    }
+    else {
+        StandaloneSignature::from_bytes(cursor)
+    };
 
-    Ok(contents)
+    signature.map_err(SignatureError::Signature)
 }
 
 // Find the path where the GPG key should be stored.
@@ -146,16 +364,7 @@ fn get_public_key_path() -> Result<PathBuf, SignatureError> {
         path
     }
     else {
-        let mut path = dirs::data_dir()
-            .ok_or_else(|| SignatureError::NoSharedDataDir)?;
-
-        // Ensure that the data dir exists
-        if !path.exists() || !path.is_dir() {
-            return Err(SignatureError::NoSharedDataDirExists(path));
-        }
-
-        path = path.join(env!("CARGO_PKG_NAME"));
-        path = path.join(HASHICORP_GPG_KEY_FILENAME);
+        let path = public_key_path()?;
 
         // Ensure that the GPG key exists
         if !path.exists() || !path.is_file() {
@@ -168,21 +377,213 @@ fn get_public_key_path() -> Result<PathBuf, SignatureError> {
     Ok(path)
 }
 
-// Locate and read the GPG key.
+/// Returns the path that the HashiCorp GPG key is read from when it isn't
+/// embedded. The key may not exist at this path.
+///
+/// # Errors
+///
+/// Errors if the shared data directory can't be found or doesn't exist.
+pub fn public_key_path() -> Result<PathBuf, SignatureError> {
+    let path = dirs::data_dir()
+        .ok_or_else(|| SignatureError::NoSharedDataDir)?;
+
+    // Ensure that the data dir exists
+    if !path.exists() || !path.is_dir() {
+        return Err(SignatureError::NoSharedDataDirExists(path));
+    }
+
+    let path = path
+        .join(env!("CARGO_PKG_NAME"))
+        .join(HASHICORP_GPG_KEY_FILENAME);
+
+    Ok(path)
+}
+
+/// Returns the fingerprint of the given armored or binary `public_key` as
+/// an uppercase hex string.
+///
+/// # Errors
+///
+/// Returns [`SignatureError::PublicKey`] if the `public_key` can't be
+/// parsed.
+pub fn fingerprint(public_key: impl AsRef<[u8]>) -> Result<String, SignatureError> {
+    let public_key = parse_public_key(public_key.as_ref())?;
+
+    Ok(hex::encode_upper(public_key.fingerprint().as_bytes()))
+}
+
+/// Returns the fingerprint of the GPG key that signatures are verified
+/// against. If `path` is given, the key at that path is used, otherwise the
+/// embedded or stored HashiCorp GPG key is used.
+///
+/// # Errors
+///
+/// Errors if:
+///   - The GPG key can't be found or parsed
+///   - The HashiCorp GPG key doesn't match
+///     [`HASHICORP_GPG_KEY_FINGERPRINT`]
+pub fn public_key_fingerprint(
+    path: Option<&Path>,
+) -> Result<String, SignatureError> {
+    let public_key = get_public_key(path)?;
+
+    fingerprint(&public_key)
+}
+
+/// Stores the given armored or binary `public_key` at the [`public_key_path`],
+/// creating the directory if required, and returns the path it was written
+/// to. The `public_key` is validated before being written.
+///
+/// # Errors
+///
+/// Errors if:
+///   - The `public_key` can't be parsed
+///   - The `public_key` doesn't match [`HASHICORP_GPG_KEY_FINGERPRINT`]
+///   - The shared data directory can't be found
+///   - Failing to write the `public_key`
+pub fn store_public_key(
+    public_key: impl AsRef<[u8]>,
+) -> Result<PathBuf, SignatureError> {
+    let public_key = public_key.as_ref();
+
+    // Ensure we only ever store the HashiCorp key.
+    verify_fingerprint(public_key)?;
+
+    let path = public_key_path()?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    fs::write(&path, public_key)?;
+
+    Ok(path)
+}
+
+/// Replaces the key at the [`public_key_path`] with the given armored or
+/// binary `public_key`, which is validated as for [`store_public_key`]. Any
+/// existing key is first copied to `hashicorp.asc.bak` alongside it. The new
+/// key is renamed into place, so there's never a partially written key at the
+/// [`public_key_path`].
+///
+/// Returns the path the `public_key` was written to, and the path of the
+/// backup if there was an existing key.
+///
+/// # Errors
+///
+/// Errors if:
+///   - The `public_key` can't be parsed
+///   - The `public_key` doesn't match [`HASHICORP_GPG_KEY_FINGERPRINT`]
+///   - The shared data directory can't be found
+///   - Failing to back up the existing key
+///   - Failing to write the `public_key`
+pub fn replace_public_key(
+    public_key: impl AsRef<[u8]>,
+) -> Result<(PathBuf, Option<PathBuf>), SignatureError> {
+    let public_key = public_key.as_ref();
+
+    // Ensure we only ever store the HashiCorp key.
+    verify_fingerprint(public_key)?;
+
+    let path   = public_key_path()?;
+    let backup = replace_file(&path, public_key)?;
+
+    Ok((path, backup))
+}
+
+// Replace the file at `path` with `content` by renaming a temporary file into
+// place, first copying any existing file to a backup alongside it. Returns
+// the path of the backup, if there was an existing file.
+fn replace_file(
+    path: &Path,
+    content: &[u8],
+) -> Result<Option<PathBuf>, SignatureError> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _                                        => Path::new("."),
+    };
+
+    fs::create_dir_all(dir)?;
+
+    let backup = if path.is_file() {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(BACKUP_SUFFIX);
+
+        let backup = PathBuf::from(backup);
+        fs::copy(path, &backup)?;
+
+        Some(backup)
+    }
+    else {
+        None
+    };
+
+    let mut staged = tempfile::NamedTempFile::new_in(dir)?;
+    staged.write_all(content)?;
+    staged.as_file().sync_all()?;
+    staged.persist(path).map_err(|err| err.error)?;
+
+    Ok(backup)
+}
+
+// Ensure that the `public_key` is the HashiCorp GPG key, refusing to use a
+// key that has been tampered with or replaced.
+fn verify_fingerprint(public_key: &[u8]) -> Result<(), SignatureError> {
+    let actual = fingerprint(public_key)?;
+
+    if actual != HASHICORP_GPG_KEY_FINGERPRINT {
+        return Err(SignatureError::FingerprintMismatch {
+            expected: HASHICORP_GPG_KEY_FINGERPRINT.to_string(),
+            actual:   actual,
+        });
+    }
+
+    Ok(())
+}
+
+// Read and validate the armored or binary GPG key at the explicitly given
+// `path`.
+fn read_public_key(path: &Path) -> Result<Vec<u8>, SignatureError> {
+    if !path.is_file() {
+        return Err(SignatureError::GpgKey(path.to_path_buf()));
+    }
+
+    let public_key = fs::read(path)
+        .map_err(|_err| SignatureError::GpgKey(path.to_path_buf()))?;
+
+    fingerprint(&public_key)
+        .map_err(|_err| SignatureError::GpgKey(path.to_path_buf()))?;
+
+    Ok(public_key)
+}
+
+// Locate and read the GPG key. An explicitly given `path` takes priority,
+// otherwise the stored key must be the HashiCorp GPG key.
 #[cfg(not(feature = "embed_gpg_key"))]
-fn get_public_key() -> Result<String, SignatureError> {
+fn get_public_key(path: Option<&Path>) -> Result<Vec<u8>, SignatureError> {
+    if let Some(path) = path {
+        return read_public_key(path);
+    }
+
     let path       = get_public_key_path()?;
-    let public_key = read_file_content(&path)?;
+    let public_key = fs::read(path)?;
+
+    verify_fingerprint(&public_key)?;
 
     Ok(public_key)
 }
 
-// Allow the wrap here, since this is for simplicity when toggling the
-// embed_gpg_key feature.
+// Read the GPG key. An explicitly given `path` takes priority, otherwise the
+// embedded key must be the HashiCorp GPG key.
 #[cfg(feature = "embed_gpg_key")]
-#[allow(clippy::unnecessary_wraps)]
-fn get_public_key() -> Result<String, SignatureError> {
-    let public_key = HASHICORP_GPG_KEY.to_string();
+fn get_public_key(path: Option<&Path>) -> Result<Vec<u8>, SignatureError> {
+    if let Some(path) = path {
+        return read_public_key(path);
+    }
+
+    let public_key = HASHICORP_GPG_KEY.as_bytes().to_vec();
+
+    verify_fingerprint(&public_key)?;
 
     Ok(public_key)
 }
@@ -190,7 +591,20 @@ fn get_public_key() -> Result<String, SignatureError> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::Path;
+    use std::fs::File;
+    use std::io::prelude::*;
+    use std::io::BufReader;
+
+    // Read a file's content into a String
+    fn read_file_content(path: &Path) -> Result<String, SignatureError> {
+        let file         = File::open(path)?;
+        let mut reader   = BufReader::new(file);
+        let mut contents = String::new();
+
+        reader.read_to_string(&mut contents)?;
+
+        Ok(contents)
+    }
 
     // Read a file's contents into Bytes
     fn read_file_bytes(path: &PathBuf) -> Result<Bytes, SignatureError> {
@@ -203,6 +617,160 @@ mod tests {
         Ok(Bytes::from(contents))
     }
 
+    #[test]
+    fn test_fingerprint() {
+        let gpg_key_file_path = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/gpg/hashicorp.asc",
+        )).to_path_buf();
+
+        let gpg_key_content = read_file_content(&gpg_key_file_path).unwrap();
+        let fingerprint     = fingerprint(&gpg_key_content).unwrap();
+
+        assert_eq!(fingerprint, "C874011F0AB405110D02105534365D9472D7468F")
+    }
+
+    #[test]
+    fn test_with_public_key_path() {
+        let test_data_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test-data/",
+        );
+
+        let gpg_key_file_path = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/gpg/hashicorp.asc",
+        ));
+
+        let signature_file_path = Path::new(&format!(
+            "{}{}",
+            test_data_path,
+            "terraform_0.15.1_SHA256SUMS.sig",
+        )).to_path_buf();
+
+        let signature_content = read_file_bytes(&signature_file_path).unwrap();
+        let signature         = Signature::with_public_key_path(
+            signature_content,
+            gpg_key_file_path,
+        );
+
+        assert!(signature.is_ok())
+    }
+
+    #[test]
+    fn test_eq() {
+        let gpg_key_file_path = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/gpg/hashicorp.asc",
+        ));
+
+        let signature = |filename: &str| {
+            let path = Path::new(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/test-data/",
+            )).join(filename);
+
+            let content = read_file_bytes(&path).unwrap();
+
+            Signature::with_public_key_path(content, gpg_key_file_path).unwrap()
+        };
+
+        let a = signature("terraform_0.15.1_SHA256SUMS.sig");
+        let b = signature("terraform_0.15.1_SHA256SUMS.sig");
+        let c = signature("terraform_0.12.26_SHA256SUMS.sig");
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+
+        // The required key ID doesn't affect equality.
+        let b = b.require_key_id("34365D9472D7468F").unwrap();
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_replace_file() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hcdl").join(HASHICORP_GPG_KEY_FILENAME);
+
+        // Nothing to back up the first time.
+        let backup = replace_file(&path, b"old key").unwrap();
+
+        assert_eq!(backup, None);
+        assert_eq!(fs::read(&path).unwrap(), b"old key");
+
+        let backup = replace_file(&path, b"new key").unwrap().unwrap();
+
+        assert_eq!(backup, dir.path().join("hcdl").join("hashicorp.asc.bak"));
+        assert_eq!(fs::read(&backup).unwrap(), b"old key");
+        assert_eq!(fs::read(&path).unwrap(), b"new key");
+
+        // Only the key and its backup are left behind.
+        assert_eq!(fs::read_dir(dir.path().join("hcdl")).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_with_public_key_path_missing() {
+        let path = Path::new("/nonexistent/hashicorp.asc");
+
+        let signature = Signature::with_public_key_path(
+            Bytes::new(),
+            path,
+        );
+
+        assert!(matches!(
+            signature.unwrap_err(),
+            SignatureError::GpgKey(p) if p == path,
+        ))
+    }
+
+    #[test]
+    fn test_with_public_key_path_unparseable() {
+        let path = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test-data/test.txt",
+        ));
+
+        let signature = Signature::with_public_key_path(
+            Bytes::new(),
+            path,
+        );
+
+        assert!(matches!(
+            signature.unwrap_err(),
+            SignatureError::GpgKey(p) if p == path,
+        ))
+    }
+
+    #[test]
+    fn test_public_key_fingerprint() {
+        let fingerprint = public_key_fingerprint(None).unwrap();
+
+        assert_eq!(fingerprint, HASHICORP_GPG_KEY_FINGERPRINT)
+    }
+
+    #[test]
+    fn test_verify_fingerprint_mismatch() {
+        // Any valid key that isn't the HashiCorp key will do.
+        let public_key = read_file_content(Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test-data/other-key.asc",
+        ))).unwrap();
+
+        let ret = verify_fingerprint(public_key.as_bytes());
+
+        assert!(matches!(
+            ret.unwrap_err(),
+            SignatureError::FingerprintMismatch { expected, .. }
+                if expected == HASHICORP_GPG_KEY_FINGERPRINT,
+        ))
+    }
+
+    #[test]
+    fn test_fingerprint_bad_public_key() {
+        assert!(fingerprint("bad").is_err())
+    }
+
     #[test]
     fn test_signature_check_ok() {
         let gpg_path = concat!(
@@ -243,9 +811,179 @@ mod tests {
         let shasums_content = read_file_content(&shasums_file_path).unwrap();
         let shasums         = Shasums::new(shasums_content);
 
-        let res = signature.check(&shasums);
+        let report = signature.check(&shasums).unwrap();
+
+        // The key ID is the low 64 bits of a V4 fingerprint.
+        assert_eq!(report.fingerprint.len(), 40);
+        assert!(report.fingerprint.ends_with(&report.key_id));
+        assert!(report.created.is_some())
+    }
+
+    #[test]
+    fn test_signature_check_bytes() {
+        let gpg_key_file_path = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/gpg/hashicorp.asc",
+        ));
+
+        let test_data_path = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test-data/",
+        ));
+
+        let signature_content = read_file_bytes(
+            &test_data_path.join("terraform_0.15.1_SHA256SUMS.sig"),
+        ).unwrap();
+
+        let signature = Signature::with_public_key_path(
+            signature_content,
+            gpg_key_file_path,
+        ).unwrap();
+
+        let data    = fs::read_to_string(test_data_path.join("terraform_0.15.1_SHA256SUMS")).unwrap();
+        let report  = signature.check_bytes(data.as_bytes()).unwrap();
+        let shasums = Shasums::new(data);
+
+        // Checking the shasums is the same as checking their bytes.
+        assert_eq!(report, signature.check(&shasums).unwrap());
+
+        // Any other data doesn't verify.
+        assert!(matches!(
+            signature.check_bytes(b"not the signed data").unwrap_err(),
+            SignatureError::Verification,
+        ))
+    }
+
+    #[test]
+    fn test_signature_check_required_key() {
+        let gpg_key_file_path = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/gpg/hashicorp.asc",
+        )).to_path_buf();
+
+        let signature_file_path = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test-data/terraform_0.15.1_SHA256SUMS.sig",
+        )).to_path_buf();
+
+        let shasums_file_path = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test-data/terraform_0.15.1_SHA256SUMS",
+        )).to_path_buf();
+
+        let gpg_key_content   = read_file_content(&gpg_key_file_path).unwrap();
+        let signature_content = read_file_bytes(&signature_file_path).unwrap();
+        let shasums_content   = read_file_content(&shasums_file_path).unwrap();
+        let shasums           = Shasums::new(shasums_content);
+
+        let signature = |key_id: &str| {
+            Signature::with_public_key(
+                signature_content.clone(),
+                &gpg_key_content,
+            )
+            .unwrap()
+            .require_key_id(key_id)
+            .unwrap()
+        };
+
+        let key_id = Signature::with_public_key(
+            signature_content.clone(),
+            &gpg_key_content,
+        )
+        .unwrap()
+        .check(&shasums)
+        .unwrap()
+        .key_id;
+
+        assert!(signature(&key_id).check(&shasums).is_ok());
+        assert!(signature(&key_id[8..]).check(&shasums).is_ok());
+
+        let res = signature("DEADBEEF").check(&shasums);
+
+        assert!(matches!(
+            res.unwrap_err(),
+            SignatureError::KeyMismatch { expected, actual }
+                if expected == "DEADBEEF" && actual == key_id,
+        ))
+    }
+
+    #[test]
+    fn test_parse_key_id() {
+        let tests = vec![
+            ("72d7468f", Some("72D7468F")),
+            ("0x34365D9472D7468F", Some("34365D9472D7468F")),
+            (HASHICORP_GPG_KEY_FINGERPRINT, Some(HASHICORP_GPG_KEY_FINGERPRINT)),
+            ("72D7468", None),
+            ("XXD7468F", None),
+            ("", None),
+        ];
+
+        for (key_id, expected) in tests {
+            let res = parse_key_id(key_id);
+
+            match expected {
+                Some(expected) => assert_eq!(res.unwrap(), expected),
+                None           => assert!(matches!(
+                    res.unwrap_err(),
+                    SignatureError::InvalidKeyId(_),
+                )),
+            }
+        }
+    }
+
+    #[test]
+    fn test_verification_report_clock_skew() {
+        let created = "2021-04-20T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let report  = VerificationReport {
+            created:     Some(created),
+            fingerprint: "C874011F0AB405110D02105534365D9472D7468F".into(),
+            key_id:      "34365D9472D7468F".into(),
+        };
+
+        let hours = |hours: i64| chrono::Duration::hours(hours);
+        let epoch = DateTime::from_timestamp(0, 0).unwrap();
+
+        let tests = vec![
+            // A clock reset to the epoch, as seen in some containers.
+            (epoch, true),
+            (created - hours(48), true),
+            (created - hours(12), false),
+            (created, false),
+            (created + hours(24 * 365), false),
+            (created + hours(24 * 365 * 30), true),
+        ];
+
+        for (now, skewed) in tests {
+            let skew = report.clock_skew(now, DEFAULT_MAX_CLOCK_SKEW);
 
-        assert!(res.is_ok())
+            assert_eq!(skew.is_some(), skewed, "now: {now}");
+        }
+
+        assert_eq!(
+            report.clock_skew(created - hours(48), DEFAULT_MAX_CLOCK_SKEW),
+            Some(ClockSkew::Future(Duration::from_secs(48 * 60 * 60))),
+        );
+
+        let no_created = VerificationReport {
+            created: None,
+            ..report
+        };
+
+        assert_eq!(
+            no_created.clock_skew(epoch, DEFAULT_MAX_CLOCK_SKEW),
+            None,
+        );
+    }
+
+    #[test]
+    fn test_verification_report_short_key_id() {
+        let report = VerificationReport {
+            created:     None,
+            fingerprint: "C874011F0AB405110D02105534365D9472D7468F".into(),
+            key_id:      "34365D9472D7468F".into(),
+        };
+
+        assert_eq!(report.short_key_id(), "72D7468F")
     }
 
     #[test]
@@ -263,14 +1001,99 @@ mod tests {
 
         let signature_content = read_file_bytes(&signature_file_path).unwrap();
         let signature         = Signature::with_public_key(
+            Bytes::from(signature_content.clone()),
+            "bad",
+        );
+
+        assert!(matches!(
+            signature.unwrap_err(),
+            SignatureError::PublicKey(_),
+        ));
+
+        let signature = Signature::with_public_key(
             Bytes::from(signature_content),
-            "bad".into(),
+            "-----BEGIN PGP PUBLIC KEY BLOCK-----\nbad",
         );
 
-        assert_eq!(
-            signature.unwrap_err().to_string(),
-            "\"failed reading: armor header: not enough bytes\"",
-        )
+        assert!(matches!(
+            signature.unwrap_err(),
+            SignatureError::PublicKey(_),
+        ))
+    }
+
+    #[test]
+    fn test_signature_bad_signature_data() {
+        let gpg_key_content = read_file_content(Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/gpg/hashicorp.asc",
+        ))).unwrap();
+
+        let signature = Signature::with_public_key(
+            Bytes::from_static(b"bad"),
+            &gpg_key_content,
+        );
+
+        assert!(matches!(
+            signature.unwrap_err(),
+            SignatureError::Signature(_),
+        ))
+    }
+
+    #[test]
+    fn test_signature_check_binary_public_key() {
+        let test_data_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test-data/",
+        );
+
+        let gpg_key_file_path = Path::new(&format!(
+            "{}{}",
+            test_data_path,
+            "hashicorp.gpg",
+        )).to_path_buf();
+
+        let signature_file_path = Path::new(&format!(
+            "{}{}",
+            test_data_path,
+            "terraform_0.15.1_SHA256SUMS.sig",
+        )).to_path_buf();
+
+        let signature_content = read_file_bytes(&signature_file_path).unwrap();
+        let signature         = Signature::with_public_key_path(
+            signature_content,
+            &gpg_key_file_path,
+        ).unwrap();
+
+        let shasums_file_path = Path::new(&format!(
+            "{}{}",
+            test_data_path,
+            "terraform_0.15.1_SHA256SUMS",
+        )).to_path_buf();
+
+        let shasums_content = read_file_content(&shasums_file_path).unwrap();
+        let shasums         = Shasums::new(shasums_content);
+
+        assert!(signature.check(&shasums).is_ok())
+    }
+
+    #[test]
+    fn test_fingerprint_binary_public_key() {
+        let public_key = fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test-data/hashicorp.gpg",
+        )).unwrap();
+
+        let fingerprint = fingerprint(&public_key).unwrap();
+
+        assert_eq!(fingerprint, HASHICORP_GPG_KEY_FINGERPRINT)
+    }
+
+    #[test]
+    fn test_is_armored() {
+        assert!(is_armored(b"-----BEGIN PGP SIGNATURE-----\n"));
+        assert!(is_armored(b"\n  -----BEGIN PGP PUBLIC KEY BLOCK-----\n"));
+        assert!(!is_armored(b"\x99\x02"));
+        assert!(!is_armored(b""));
     }
 
     #[test]
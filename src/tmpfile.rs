@@ -1,23 +1,61 @@
 // Handles a tmpfile for downloading
 use super::error::TmpFileError;
-use std::fs::OpenOptions;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::fs::{
+    self,
+    File,
+    OpenOptions,
+};
 use std::io::{
     self,
     BufWriter,
     Seek,
     SeekFrom,
+    Write,
+};
+use std::path::{
+    Path,
+    PathBuf,
+};
+use tempfile::{
+    NamedTempFile,
+    TempPath,
 };
-use std::path::Path;
-use tempfile::NamedTempFile;
 
 #[cfg(target_family = "unix")]
-use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::fs::PermissionsExt;
+
+// Suffix of the file holding the content of a resumable download.
+const PARTIAL_SUFFIX: &str = ".part";
+
+// Suffix of the sidecar recording the state of a resumable download, next to
+// its partial file.
+const SIDECAR_SUFFIX: &str = ".hcdl-partial";
 
 /// Wrapper for a [`tempfile::NamedTempFile`].
 #[derive(Debug)]
 pub struct TmpFile {
     tmpfile:  NamedTempFile,
     filename: String,
+    partial:  Option<PathBuf>,
+    linked:   bool,
+    sha256:   Option<String>,
+}
+
+// The state of a resumable download, as recorded in its sidecar.
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct Partial {
+    // The URL being downloaded.
+    pub(crate) url: String,
+
+    // The total size of the content, if the server advertised it.
+    pub(crate) size: Option<u64>,
+
+    // The number of bytes downloaded that can be resumed from.
+    pub(crate) bytes: u64,
 }
 
 impl TmpFile {
@@ -30,6 +68,89 @@ impl TmpFile {
         let tmp = Self {
             filename: filename.to_owned(),
             tmpfile:  NamedTempFile::new()?,
+            partial:  None,
+            linked:   false,
+            sha256:   None,
+        };
+
+        Ok(tmp)
+    }
+
+    /// Make a new [`TmpFile`] for filename, with the underlying temporary
+    /// file created in `dir` rather than the system temp directory.
+    ///
+    /// # Errors
+    ///
+    /// Can error if unable to create a [`NamedTempFile`] in `dir`.
+    pub fn new_in(filename: &str, dir: &Path) -> Result<Self, TmpFileError> {
+        let tmp = Self {
+            filename: filename.to_owned(),
+            tmpfile:  NamedTempFile::new_in(dir)?,
+            partial:  None,
+            linked:   false,
+            sha256:   None,
+        };
+
+        Ok(tmp)
+    }
+
+    /// Make a new [`TmpFile`] for filename in `dir`, like
+    /// [`TmpFile::new_in`], whose content can outlive the process so that
+    /// [`crate::client::Client::download`] can resume it in a later run.
+    ///
+    /// Content left to resume is kept in a hidden `.part` file in `dir`,
+    /// linked to the temporary file, alongside a `.hcdl-partial` sidecar
+    /// recording how much of it was downloaded. Neither is created until a
+    /// failed download leaves content to resume, and both are removed once
+    /// the download completes or fails in a way that can't be resumed.
+    ///
+    /// The tmpfile is locked, so that only one run at a time downloads into
+    /// a `.part` file, any other run gets a tmpfile that isn't resumable.
+    /// Where the filesystem doesn't support hard links, or on platforms
+    /// other than unix, this is the same as [`TmpFile::new_in`].
+    ///
+    /// # Errors
+    ///
+    /// Can error if unable to create a [`NamedTempFile`] in `dir`.
+    pub fn resumable_in(
+        filename: &str,
+        dir: &Path,
+    ) -> Result<Self, TmpFileError> {
+        let partial = dir.join(format!(".{filename}{PARTIAL_SUFFIX}"));
+
+        // Pick up the content left by an earlier run, if there is any.
+        let linked = tempfile::Builder::new()
+            .make_in(dir, |path| {
+                fs::hard_link(&partial, path)?;
+
+                OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .open(path)
+            });
+
+        let (tmpfile, linked) = match linked {
+            Ok(tmpfile) => (tmpfile, true),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                (NamedTempFile::new_in(dir)?, false)
+            },
+            Err(_err) => return Self::new_in(filename, dir),
+        };
+
+        // Another run holding the lock is using the partial file. Our link
+        // to it is removed with the tmpfile.
+        if !try_lock(tmpfile.as_file())? {
+            drop(tmpfile);
+
+            return Self::new_in(filename, dir);
+        }
+
+        let tmp = Self {
+            filename: filename.to_owned(),
+            tmpfile:  tmpfile,
+            partial:  Some(partial),
+            linked:   linked,
+            sha256:   None,
         };
 
         Ok(tmp)
@@ -41,18 +162,163 @@ impl TmpFile {
         &self.filename
     }
 
+    /// Returns `true` if the tmpfile contains no data.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the `tmpfile` metadata can't be read.
+    pub fn is_empty(&self) -> Result<bool, TmpFileError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Return the current size of the tmpfile in bytes.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the `tmpfile` metadata can't be read.
+    pub fn len(&self) -> Result<u64, TmpFileError> {
+        let len = self.tmpfile.as_file().metadata()?.len();
+
+        Ok(len)
+    }
+
     /// Return a [`NamedTempFile`] handle that has been rewound to 0.
     ///
+    /// Since the content may be changed through the handle, any SHA256
+    /// calculated while the content was written is forgotten.
+    ///
     /// # Errors
     ///
     /// Can error if seeking to the beginning of the `tmpfile` fails.
     pub fn handle(&mut self) -> Result<&mut NamedTempFile, TmpFileError> {
+        self.sha256 = None;
         self.tmpfile.seek(SeekFrom::Start(0))?;
 
         Ok(&mut self.tmpfile)
     }
 
-    /// Persist the file into our current directory as self.filename
+    /// Consume the [`TmpFile`], returning the underlying [`NamedTempFile`]
+    /// and the filename it was created for. The file is left at whatever
+    /// position it was last read or written at.
+    ///
+    /// The file is still deleted when the [`NamedTempFile`] is dropped,
+    /// unless it's persisted.
+    #[must_use]
+    pub fn into_file(self) -> (NamedTempFile, String) {
+        (self.tmpfile, self.filename)
+    }
+
+    /// Consume the [`TmpFile`], closing the file and returning its
+    /// [`TempPath`]. The content is synced to disk first, so that it's
+    /// complete if the path is persisted.
+    ///
+    /// # Errors
+    ///
+    /// Can error if syncing the `tmpfile` to disk fails.
+    pub fn into_temp_path(self) -> Result<TempPath, TmpFileError> {
+        self.tmpfile.as_file().sync_all()?;
+
+        Ok(self.tmpfile.into_temp_path())
+    }
+
+    /// Returns `true` if the content of the tmpfile can be resumed from in a
+    /// later run, see [`TmpFile::resumable_in`].
+    #[must_use]
+    pub fn is_resumable(&self) -> bool {
+        self.partial.is_some()
+    }
+
+    // Path of the sidecar recording the state of a resumable download.
+    fn sidecar(&self) -> Option<PathBuf> {
+        self.partial.as_ref().map(|partial| {
+            let mut sidecar = partial.clone().into_os_string();
+            sidecar.push(SIDECAR_SUFFIX);
+
+            PathBuf::from(sidecar)
+        })
+    }
+
+    // Load the state recorded in the sidecar, if there is one that can be
+    // read.
+    pub(crate) fn load_partial(&self) -> Option<Partial> {
+        let content = fs::read(self.sidecar()?).ok()?;
+
+        serde_json::from_slice(&content).ok()
+    }
+
+    // Record the state of the download in the sidecar, linking the partial
+    // file to the tmpfile first if it isn't yet. Does nothing if the tmpfile
+    // isn't resumable, or stops being so because another run created the
+    // partial file in the meantime.
+    pub(crate) fn save_partial(
+        &mut self,
+        partial: &Partial,
+    ) -> Result<(), TmpFileError> {
+        let Some(path) = &self.partial else {
+            return Ok(());
+        };
+
+        if !self.linked {
+            match fs::hard_link(self.tmpfile.path(), path) {
+                Ok(()) => self.linked = true,
+                // Another run created the partial file since we looked.
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                    self.partial = None;
+
+                    return Ok(());
+                },
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        if let Some(sidecar) = self.sidecar() {
+            let content = serde_json::to_vec(partial).map_err(io::Error::from)?;
+
+            fs::write(sidecar, content)?;
+        }
+
+        Ok(())
+    }
+
+    // Remove the partial file and sidecar once the download is complete, or
+    // can't be resumed, leaving an ordinary tmpfile. Those of another run are
+    // left alone.
+    pub(crate) fn remove_partial(&mut self) -> Result<(), TmpFileError> {
+        let sidecar = self.sidecar();
+        let partial = self.partial.take();
+
+        if !std::mem::take(&mut self.linked) {
+            return Ok(());
+        }
+
+        for path in [sidecar, partial].into_iter().flatten() {
+            if let Err(err) = fs::remove_file(&path) {
+                if err.kind() != io::ErrorKind::NotFound {
+                    return Err(err.into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Return the hex encoded SHA256 of the content, if it was calculated
+    /// while the content was written. When it wasn't, the content must be
+    /// hashed by reading it back.
+    #[must_use]
+    pub fn sha256(&self) -> Option<&str> {
+        self.sha256.as_deref()
+    }
+
+    // Record the hex encoded SHA256 of the content, calculated while the
+    // content was written.
+    pub(crate) fn set_sha256(&mut self, sha256: String) {
+        self.sha256 = Some(sha256);
+    }
+
+    /// Persist the file into `dir` as self.filename, or into our current
+    /// directory if no `dir` is given. Returns the absolute path of the
+    /// persisted file.
     ///
     /// # Errors
     ///
@@ -60,25 +326,365 @@ impl TmpFile {
     ///   - Failure to open file for writing
     ///   - Attempting to get the file handle for the `tmpfile`
     ///   - Issues while writing to the `tmpfile`
-    pub fn persist(&mut self) -> Result<(), TmpFileError> {
-        let dest        = Path::new(&self.filename);
-        let mut options = OpenOptions::new();
+    ///   - Failure to resolve the absolute path of the persisted file
+    pub fn persist(
+        &mut self,
+        dir: Option<&Path>,
+    ) -> Result<PathBuf, TmpFileError> {
+        let dir  = dir.unwrap_or_else(|| Path::new("."));
+        let dest = dir.join(&self.filename);
+
+        self.persist_to(&dest)?;
+
+        let dest = fs::canonicalize(&dest)?;
+
+        Ok(dest)
+    }
+
+    /// Persist a copy of the file to `dest`.
+    ///
+    /// The file is staged next to `dest` and renamed into place, so a
+    /// partially written `dest` is never observed. When the tmpfile is on the
+    /// same filesystem as `dest` it's staged with a hard link, otherwise its
+    /// content is copied and synced first.
+    ///
+    /// # Errors
+    ///
+    /// Can error under the same conditions as [`TmpFile::persist`].
+    pub fn persist_to(&mut self, dest: &Path) -> Result<(), TmpFileError> {
+        let dir = match dest.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _                                        => Path::new("."),
+        };
 
         // Keep file around with -rw-r--r-- permissions.
         #[cfg(target_family = "unix")]
-        options.mode(0o644);
+        self.tmpfile
+            .as_file()
+            .set_permissions(fs::Permissions::from_mode(0o644))?;
 
-        let writer = options
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(dest)?;
+        self.tmpfile.as_file().sync_all()?;
 
-        let mut writer = BufWriter::new(writer);
-        let mut handle = self.handle()?;
+        let source = self.tmpfile.path().to_path_buf();
+        let linked = tempfile::Builder::new()
+            .make_in(dir, |path| fs::hard_link(&source, path));
 
-        io::copy(&mut handle, &mut writer)?;
+        let staged = match linked {
+            Ok(staged) => staged.into_temp_path(),
+            Err(_err)  => self.copy_in(dir, dest)?,
+        };
+
+        let staged_path = staged.to_path_buf();
+
+        staged.persist(dest).map_err(|err| err.error)?;
+
+        // Renaming a hard link over another link to the same file does
+        // nothing, leaving the staged link behind when `dest` was already
+        // persisted from this tmpfile.
+        if staged_path.exists() {
+            fs::remove_file(&staged_path)?;
+        }
 
         Ok(())
     }
+
+    // Copy the content of the tmpfile to a new temporary file in `dir`,
+    // syncing it to disk, ready to be renamed to `dest`.
+    fn copy_in(
+        &mut self,
+        dir: &Path,
+        dest: &Path,
+    ) -> Result<TempPath, TmpFileError> {
+        let disk_full = |err: io::Error| {
+            if is_disk_full(&err) {
+                TmpFileError::DiskFull(dest.to_path_buf())
+            }
+            else {
+                TmpFileError::IoError(err)
+            }
+        };
+
+        let staged     = NamedTempFile::new_in(dir)?;
+        let mut writer = BufWriter::new(staged.as_file());
+        let mut handle = self.handle()?;
+
+        io::copy(&mut handle, &mut writer).map_err(disk_full)?;
+        writer.flush().map_err(disk_full)?;
+        drop(writer);
+
+        #[cfg(target_family = "unix")]
+        staged
+            .as_file()
+            .set_permissions(fs::Permissions::from_mode(0o644))?;
+
+        staged.as_file().sync_all().map_err(disk_full)?;
+
+        Ok(staged.into_temp_path())
+    }
+}
+
+// Take an exclusive lock on `file`, without waiting for it. Returns `false`
+// if another process holds the lock.
+#[cfg(target_family = "unix")]
+fn try_lock(file: &File) -> Result<bool, TmpFileError> {
+    use rustix::fs::{
+        flock,
+        FlockOperation,
+    };
+    use rustix::io::Errno;
+
+    match flock(file, FlockOperation::NonBlockingLockExclusive) {
+        Ok(())                               => Ok(true),
+        Err(err) if err == Errno::WOULDBLOCK => Ok(false),
+        Err(err)                             => Err(io::Error::from(err).into()),
+    }
+}
+
+// Without a way to lock the file, it's never treated as locked by us.
+#[cfg(not(target_family = "unix"))]
+fn try_lock(_file: &File) -> Result<bool, TmpFileError> {
+    Ok(false)
+}
+
+// Returns `true` if `err` was caused by the disk being full.
+pub(crate) fn is_disk_full(err: &io::Error) -> bool {
+    // ENOSPC
+    #[cfg(target_family = "unix")]
+    let codes = [28];
+
+    // ERROR_HANDLE_DISK_FULL and ERROR_DISK_FULL
+    #[cfg(target_family = "windows")]
+    let codes = [39, 112];
+
+    #[cfg(not(any(target_family = "unix", target_family = "windows")))]
+    let codes: [i32; 0] = [];
+
+    err.raw_os_error().is_some_and(|code| codes.contains(&code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::io::{
+        Read,
+        Write,
+    };
+
+    #[test]
+    fn test_new_in() {
+        let dir         = tempfile::tempdir().unwrap();
+        let mut tmpfile = TmpFile::new_in("test.txt", dir.path()).unwrap();
+
+        tmpfile.handle().unwrap().write_all(b"test content").unwrap();
+
+        let mut content = String::new();
+        tmpfile.handle().unwrap().read_to_string(&mut content).unwrap();
+
+        assert_eq!(tmpfile.filename(), "test.txt");
+        assert_eq!(content, "test content");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_resumable_in() {
+        let dir         = tempfile::tempdir().unwrap();
+        let mut tmpfile = TmpFile::resumable_in("test.txt", dir.path()).unwrap();
+
+        assert!(tmpfile.is_resumable());
+
+        tmpfile.handle().unwrap().write_all(b"test ").unwrap();
+
+        // Nothing but the tmpfile exists until there's content to resume.
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let partial = Partial {
+            url:   "https://example.com/test.txt".into(),
+            size:  Some(12),
+            bytes: 5,
+        };
+
+        tmpfile.save_partial(&partial).unwrap();
+        drop(tmpfile);
+
+        // The content and sidecar outlive the tmpfile.
+        let mut tmpfile = TmpFile::resumable_in("test.txt", dir.path()).unwrap();
+
+        assert_eq!(tmpfile.load_partial(), Some(partial));
+        assert_eq!(tmpfile.len().unwrap(), 5);
+
+        tmpfile.remove_partial().unwrap();
+
+        assert!(!tmpfile.is_resumable());
+        assert_eq!(tmpfile.load_partial(), None);
+
+        // Only the tmpfile itself is left.
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_resumable_in_locked() {
+        let dir         = tempfile::tempdir().unwrap();
+        let mut tmpfile = TmpFile::resumable_in("test.txt", dir.path()).unwrap();
+
+        tmpfile.handle().unwrap().write_all(b"test ").unwrap();
+
+        let partial = Partial {
+            url:   "https://example.com/test.txt".into(),
+            size:  None,
+            bytes: 5,
+        };
+
+        tmpfile.save_partial(&partial).unwrap();
+
+        // A concurrent run doesn't share the partial file.
+        let mut other = TmpFile::resumable_in("test.txt", dir.path()).unwrap();
+
+        assert!(!other.is_resumable());
+        assert!(other.is_empty().unwrap());
+
+        // Nor does it clean up the partial file of the first run.
+        other.remove_partial().unwrap();
+
+        assert_eq!(tmpfile.load_partial(), Some(partial));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_save_partial_already_exists() {
+        let dir       = tempfile::tempdir().unwrap();
+        let mut first = TmpFile::resumable_in("test.txt", dir.path()).unwrap();
+        let mut other = TmpFile::resumable_in("test.txt", dir.path()).unwrap();
+
+        let partial = Partial {
+            url:   "https://example.com/test.txt".into(),
+            size:  None,
+            bytes: 5,
+        };
+
+        first.handle().unwrap().write_all(b"test ").unwrap();
+        first.save_partial(&partial).unwrap();
+
+        // The partial file created by the first run since the other looked
+        // for one is left alone.
+        let other_partial = Partial {
+            url:   "https://example.com/other.txt".into(),
+            size:  None,
+            bytes: 3,
+        };
+
+        other.handle().unwrap().write_all(b"tes").unwrap();
+        other.save_partial(&other_partial).unwrap();
+
+        assert!(!other.is_resumable());
+        assert_eq!(first.load_partial(), Some(partial));
+    }
+
+    #[test]
+    fn test_new_in_missing_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let res = TmpFile::new_in("test.txt", &dir.path().join("missing"));
+
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_into_file() {
+        let mut tmpfile = TmpFile::new("test.txt").unwrap();
+
+        tmpfile.handle().unwrap().write_all(b"test content").unwrap();
+
+        let (file, filename) = tmpfile.into_file();
+
+        assert_eq!(filename, "test.txt");
+        assert_eq!(fs::read(file.path()).unwrap(), b"test content");
+    }
+
+    #[test]
+    fn test_into_temp_path() {
+        let dir         = tempfile::tempdir().unwrap();
+        let dest        = dir.path().join("test.txt");
+        let mut tmpfile = TmpFile::new_in("test.txt", dir.path()).unwrap();
+
+        tmpfile.handle().unwrap().write_all(b"test content").unwrap();
+
+        let path = tmpfile.into_temp_path().unwrap();
+        let tmp  = path.to_path_buf();
+
+        assert_eq!(fs::read(&path).unwrap(), b"test content");
+
+        path.persist(&dest).unwrap();
+
+        assert!(!tmp.exists());
+        assert_eq!(fs::read(&dest).unwrap(), b"test content");
+    }
+
+    #[test]
+    fn test_is_disk_full() {
+        #[cfg(target_family = "unix")]
+        assert!(is_disk_full(&io::Error::from_raw_os_error(28)));
+
+        assert!(!is_disk_full(&io::Error::from(io::ErrorKind::NotFound)));
+    }
+
+    #[test]
+    fn test_persist_to() {
+        let dir         = tempfile::tempdir().unwrap();
+        let dest        = dir.path().join("test.txt");
+        let mut tmpfile = TmpFile::new("test.txt").unwrap();
+
+        tmpfile.handle().unwrap().write_all(b"test content").unwrap();
+        tmpfile.persist_to(&dest).unwrap();
+
+        assert_eq!(fs::read(&dest).unwrap(), b"test content");
+
+        // Persisting again replaces the existing file.
+        tmpfile.persist_to(&dest).unwrap();
+
+        assert_eq!(fs::read(&dest).unwrap(), b"test content");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_persist() {
+        let dir         = tempfile::tempdir().unwrap();
+        let mut tmpfile = TmpFile::new("test.txt").unwrap();
+
+        tmpfile.handle().unwrap().write_all(b"test content").unwrap();
+
+        let path = tmpfile.persist(Some(dir.path())).unwrap();
+
+        assert!(path.is_absolute());
+        assert_eq!(path, fs::canonicalize(dir.path().join("test.txt")).unwrap());
+        assert_eq!(fs::read(&path).unwrap(), b"test content");
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_persist_to_same_dir_renames() {
+        use std::os::unix::fs::MetadataExt;
+
+        let dir         = tempfile::tempdir().unwrap();
+        let dest        = dir.path().join("test.txt");
+        let mut tmpfile = TmpFile::new_in("test.txt", dir.path()).unwrap();
+
+        tmpfile.handle().unwrap().write_all(b"test content").unwrap();
+        tmpfile.persist_to(&dest).unwrap();
+
+        // On the same filesystem the persisted file is the tmpfile itself,
+        // renamed into place rather than copied.
+        let persisted = fs::metadata(&dest).unwrap();
+        let tmp       = tmpfile.handle().unwrap().as_file().metadata().unwrap();
+
+        assert_eq!(persisted.ino(), tmp.ino());
+        assert_eq!(persisted.mode() & 0o777, 0o644);
+
+        // The tmpfile is still usable after being persisted.
+        let mut content = String::new();
+        tmpfile.handle().unwrap().read_to_string(&mut content).unwrap();
+
+        assert_eq!(content, "test content");
+    }
 }